@@ -0,0 +1,34 @@
+use ramemu::compiled::Compiled;
+use ramemu::program::Program;
+use ramemu::ram::Ram;
+use std::time::Instant;
+
+const SOURCE: &str = "
+  LOAD =1000000
+  loop: SUB =1
+  STORE 1
+  JGTZ loop
+  HALT
+";
+
+fn main() {
+  let program = Program::from_source(SOURCE).unwrap();
+  let compiled = Compiled::try_from(&program).unwrap();
+  let new_ram = || {
+    Ram::new(
+      program.clone(),
+      Box::new(std::io::empty()),
+      Box::new(std::io::sink()),
+    )
+  };
+
+  let start = Instant::now();
+  let mut ram = new_ram();
+  ram.run().unwrap();
+  println!("interpreted: {:?}", start.elapsed());
+
+  let start = Instant::now();
+  let mut ram = new_ram();
+  ram.run_compiled(&compiled).unwrap();
+  println!("compiled:    {:?}", start.elapsed());
+}
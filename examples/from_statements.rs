@@ -5,12 +5,13 @@ use std::io::BufReader;
 use std::io::BufWriter;
 
 fn main() {
-  let program = Program::from(vec![
+  let program = Program::try_from(vec![
     Stmt::Load(Value::Pure(2), 1),
     Stmt::Add(Value::Pure(2), 3),
     Stmt::Output(Value::Pure(0), 4),
     Stmt::Halt(5),
-  ]);
+  ])
+  .unwrap();
 
   let reader = BufReader::new(std::io::empty());
   let writer = BufWriter::new(std::io::sink());
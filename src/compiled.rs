@@ -0,0 +1,228 @@
+//! The `compiled` module provides a flat representation of a [`Program`] for faster execution.
+//!
+//! Interpreting a [`Program`] resolves the label of every jump by name each time the jump is
+//! taken. [`Compiled`] resolves all jump targets to instruction indices once, up front, and
+//! stores the instructions as a compact vector of [`Op`]s. Labels are kept as [`Op::Nop`]s,
+//! so instruction indices, and with them `JIND` targets, stay the same as in the program.
+//!
+//! A [`Compiled`] program is executed with [`Ram::run_compiled`].
+//!
+//! # Examples
+//!
+//! ```
+//! use ramemu::compiled::Compiled;
+//! use ramemu::program::Program;
+//! use ramemu::ram::Ram;
+//!
+//! let program = Program::from_source("LOAD =3\nloop: SUB =1\nJGTZ loop\nHALT").unwrap();
+//! let compiled = Compiled::try_from(&program).unwrap();
+//!
+//! let mut ram = Ram::new(program, Box::new(std::io::empty()), Box::new(std::io::sink()));
+//! ram.run_compiled(&compiled).unwrap();
+//! assert_eq!(ram.accumulator(), 0);
+//! ```
+//!
+//! [`Ram::run_compiled`]: crate::ram::Ram::run_compiled
+use crate::errors::ParseError;
+use crate::parser;
+use crate::program::Program;
+use crate::stmt::Label;
+use crate::stmt::RegisterValue;
+use crate::stmt::Stmt;
+use crate::stmt::Value;
+
+/// A single instruction of a [`Compiled`] program.
+///
+/// Unlike [`Stmt`], an [`Op`] carries no line number and jumps carry the index of their
+/// target instead of a label.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Op {
+  /// See [`Stmt::Load`]
+  Load(Value),
+  /// See [`Stmt::Store`]
+  Store(RegisterValue),
+  /// See [`Stmt::Add`]
+  Add(Value),
+  /// See [`Stmt::Sub`]
+  Sub(Value),
+  /// See [`Stmt::Mul`]
+  Mul(Value),
+  /// See [`Stmt::Div`]
+  Div(Value),
+  /// See [`Stmt::Mod`]
+  Mod(Value),
+  /// See [`Stmt::Pow`]
+  Pow(Value),
+  /// See [`Stmt::Inc`]
+  Inc(RegisterValue),
+  /// See [`Stmt::Dec`]
+  Dec(RegisterValue),
+  /// See [`Stmt::Swap`]
+  Swap(RegisterValue),
+  /// Jumps to the instruction at the index
+  Jump(usize),
+  /// Jumps to the instruction at the index if register `0` is equal to `0`
+  JumpIfZero(usize),
+  /// Jumps to the instruction at the index if register `0` is greater than `0`
+  JumpGreaterZero(usize),
+  /// Jumps to the instruction at the index if register `0` is less than `0`
+  JumpLessZero(usize),
+  /// Jumps to the instruction at the index if register `0` is not equal to `0`
+  JumpNotZero(usize),
+  /// See [`Stmt::JumpIndirect`]
+  JumpIndirect(RegisterValue),
+  /// See [`Stmt::Input`]
+  Input(RegisterValue),
+  /// See [`Stmt::Output`]
+  Output(Value),
+  /// Does nothing, compiled from [`Stmt::Nop`], [`Stmt::Label`] and [`Stmt::Alias`]
+  Nop,
+  /// See [`Stmt::Halt`]
+  Halt,
+  /// See [`Stmt::HaltCode`]
+  HaltCode(i64),
+}
+
+impl Op {
+  /// Converts a statement that is not a jump to a label into an [`Op`].
+  ///
+  /// Returns `None` for `JUMP` and the conditional jumps to a label, whose targets need a
+  /// [`Program`] to be resolved.
+  pub(crate) fn from_stmt(stmt: &Stmt) -> Option<Op> {
+    let op = match stmt {
+      Stmt::Load(value, _) => Op::Load(value.clone()),
+      Stmt::Store(value, _) => Op::Store(*value),
+      Stmt::Add(value, _) => Op::Add(value.clone()),
+      Stmt::Sub(value, _) => Op::Sub(value.clone()),
+      Stmt::Mul(value, _) => Op::Mul(value.clone()),
+      Stmt::Div(value, _) => Op::Div(value.clone()),
+      Stmt::Mod(value, _) => Op::Mod(value.clone()),
+      Stmt::Pow(value, _) => Op::Pow(value.clone()),
+      Stmt::Inc(value, _) => Op::Inc(*value),
+      Stmt::Dec(value, _) => Op::Dec(*value),
+      Stmt::Swap(value, _) => Op::Swap(*value),
+      Stmt::JumpIndirect(value, _) => Op::JumpIndirect(*value),
+      Stmt::Input(value, _) => Op::Input(*value),
+      Stmt::Output(value, _) => Op::Output(value.clone()),
+      Stmt::Label(..) | Stmt::Alias(..) | Stmt::Nop(_) => Op::Nop,
+      Stmt::Halt(_) => Op::Halt,
+      Stmt::HaltCode(code, _) => Op::HaltCode(*code as i64),
+      Stmt::Jump(..)
+      | Stmt::JumpIfZero(..)
+      | Stmt::JumpGreaterZero(..)
+      | Stmt::JumpLessZero(..)
+      | Stmt::JumpNotZero(..) => return None,
+    };
+    Some(op)
+  }
+}
+
+/// A [`Program`] with all jump targets resolved, ready for [`Ram::run_compiled`].
+///
+/// [`Ram::run_compiled`]: crate::ram::Ram::run_compiled
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Compiled {
+  ops: Vec<Op>,
+  lines: Vec<usize>,
+}
+
+impl Compiled {
+  /// Returns the compiled instructions.
+  #[inline]
+  pub fn ops(&self) -> &[Op] {
+    &self.ops
+  }
+
+  /// Returns the instruction at the given index and the line it was compiled from.
+  #[inline]
+  pub(crate) fn get(&self, index: usize) -> Option<(&Op, usize)> {
+    Some((self.ops.get(index)?, self.lines[index]))
+  }
+}
+
+impl TryFrom<&Program> for Compiled {
+  type Error = Vec<ParseError>;
+
+  /// Compiles the program, failing with an [`ParseError::UnknownLabel`] for every jump to a
+  /// label that is not defined. Label addresses are resolved into pure values.
+  fn try_from(program: &Program) -> Result<Self, Self::Error> {
+    parser::validate_labels(&program.instructions)?;
+
+    let target = |label: &Label| {
+      program
+        .decode_label(label)
+        .expect("labels are validated before compiling")
+    };
+    let ops = program
+      .instructions
+      .iter()
+      .map(|stmt| match stmt {
+        Stmt::Jump(label, _) => Op::Jump(target(label)),
+        Stmt::JumpIfZero(label, _) => Op::JumpIfZero(target(label)),
+        Stmt::JumpGreaterZero(label, _) => Op::JumpGreaterZero(target(label)),
+        Stmt::JumpLessZero(label, _) => Op::JumpLessZero(target(label)),
+        Stmt::JumpNotZero(label, _) => Op::JumpNotZero(target(label)),
+        stmt => {
+          let mut stmt = stmt.clone();
+          if let Some(value) = stmt.value_mut() {
+            if let Value::LabelAddr(label) = value {
+              *value = Value::Pure(target(label) as isize);
+            }
+          }
+          Op::from_stmt(&stmt).expect("jumps to labels are handled above")
+        }
+      })
+      .collect();
+    let lines = program.instructions.iter().map(Stmt::get_line).collect();
+
+    Ok(Compiled { ops, lines })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_compile() {
+    let program = Program::from_source("start: LOAD =1\nJZERO start\nJUMP end\nend: HALT").unwrap();
+    let compiled = Compiled::try_from(&program).unwrap();
+
+    assert_eq!(
+      compiled.ops(),
+      &[
+        Op::Nop,
+        Op::Load(Value::Pure(1)),
+        Op::JumpIfZero(0),
+        Op::Jump(4),
+        Op::Nop,
+        Op::Halt,
+      ]
+    );
+    assert_eq!(compiled.get(3), Some((&Op::Jump(4), 3)));
+  }
+
+  #[test]
+  fn test_compile_label_address() {
+    let program = Program::from_source("LOAD =@end\nend: HALT").unwrap();
+    let compiled = Compiled::try_from(&program).unwrap();
+
+    assert_eq!(
+      compiled.ops(),
+      &[Op::Load(Value::Pure(1)), Op::Nop, Op::Halt]
+    );
+  }
+
+  #[test]
+  fn test_compile_unknown_label() {
+    let program = Program::from(
+      parser::parse("JUMP nowhere")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap(),
+    );
+    assert_eq!(
+      Compiled::try_from(&program),
+      Err(vec![ParseError::UnknownLabel(1, "nowhere".to_string())])
+    );
+  }
+}
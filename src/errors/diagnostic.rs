@@ -0,0 +1,89 @@
+use std::ops::Range;
+
+use codespan_reporting::diagnostic::Diagnostic;
+use codespan_reporting::diagnostic::Label;
+use codespan_reporting::diagnostic::Severity;
+
+use super::ParseError;
+
+impl ParseError {
+  /// Converts the error into a [`Diagnostic`] labelling the offending token in `source`.
+  ///
+  /// `source` must be the whole source code the error was produced from, e.g. by
+  /// [`parse`], and `file_id` identifies it in the `codespan_reporting` file database.
+  /// Errors without a [`Span`](super::Span) label the whole line.
+  ///
+  /// [`parse`]: ../parser/fn.parse.html
+  pub fn to_diagnostic<FileId>(&self, file_id: FileId, source: &str) -> Diagnostic<FileId> {
+    let message = self.to_string();
+    let message = message
+      .split_once(": ")
+      .map_or(message.as_str(), |(_, message)| message);
+
+    Diagnostic::error()
+      .with_message(message)
+      .with_labels(vec![Label::primary(file_id, self.range_in(source))])
+  }
+
+  /// Converts the error into a warning [`Diagnostic`], like [`ParseError::to_diagnostic`].
+  ///
+  /// Meant for the [`ParseOutcome::warnings`] reported by [`parse_program`].
+  ///
+  /// [`ParseOutcome::warnings`]: ../parser/struct.ParseOutcome.html#structfield.warnings
+  /// [`parse_program`]: ../parser/fn.parse_program.html
+  pub fn to_warning<FileId>(&self, file_id: FileId, source: &str) -> Diagnostic<FileId> {
+    let mut diagnostic = self.to_diagnostic(file_id, source);
+    diagnostic.severity = Severity::Warning;
+    diagnostic
+  }
+
+  /// Returns the byte range of the error inside the whole `source`.
+  fn range_in(&self, source: &str) -> Range<usize> {
+    let Some(text) = source.lines().nth(self.line().wrapping_sub(1)) else {
+      return source.len()..source.len();
+    };
+    let line_start = text.as_ptr() as usize - source.as_ptr() as usize;
+    // `parse` trims lines before parsing them, spans are relative to the trimmed line.
+    let offset = line_start + text.len() - text.trim_start().len();
+
+    match self.span() {
+      Some(span) => offset + span.start..offset + span.end,
+      None => line_start..line_start + text.len(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use codespan_reporting::diagnostic::LabelStyle;
+
+  use super::*;
+  use crate::parser::parse;
+
+  #[test]
+  fn test_to_diagnostic() {
+    let source = "LOAD =1\n  FOO 2\n";
+    let error = parse(source).find_map(Result::err).unwrap();
+    let diagnostic = error.to_diagnostic((), source);
+
+    assert_eq!(diagnostic.message, "unknown opcode 'FOO'");
+    assert_eq!(diagnostic.labels.len(), 1);
+    assert_eq!(diagnostic.labels[0].style, LabelStyle::Primary);
+    assert_eq!(&source[diagnostic.labels[0].range.clone()], "FOO");
+
+    let error = ParseError::UnknownLabel(1, "end".to_string());
+    let diagnostic = error.to_diagnostic((), source);
+    assert_eq!(diagnostic.labels[0].range, 0..7);
+  }
+
+  #[test]
+  fn test_to_warning() {
+    let source = "HALT: HALT\n";
+    let outcome = crate::parser::parse_program(source);
+    let diagnostic = outcome.warnings[0].to_warning((), source);
+
+    assert_eq!(diagnostic.severity, Severity::Warning);
+    assert_eq!(diagnostic.message, "label 'HALT' is named like an opcode");
+    assert_eq!(&source[diagnostic.labels[0].range.clone()], "HALT:");
+  }
+}
@@ -0,0 +1,72 @@
+use alloc::string::String;
+
+use crate::errors::InterpretError;
+
+/// Represents errors reported by an [`Input`] source.
+///
+/// [`Input`]: ../io/trait.Input.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InputError {
+  /// Occurs when the underlying source fails to provide data.
+  Io,
+  /// Occurs when the provided text is not a valid value. Carries the offending text.
+  InvalidInput(String),
+}
+
+impl InputError {
+  /// Converts the error into an [`InterpretError`] reported at the given line.
+  #[inline]
+  pub(crate) fn at(self, line: usize) -> InterpretError {
+    match self {
+      InputError::Io => InterpretError::IOError(line),
+      InputError::InvalidInput(input) => InterpretError::InvalidInput(line, input),
+    }
+  }
+}
+
+impl core::fmt::Display for InputError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      InputError::Io => write!(f, "I/O error"),
+      InputError::InvalidInput(input) => write!(f, "invalid input '{}'", input.trim()),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InputError {}
+
+/// Represents errors reported by an [`Output`] sink.
+///
+/// [`Output`]: ../io/trait.Output.html
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OutputError {
+  /// Occurs when the underlying sink fails to accept data.
+  Io,
+  /// Occurs when a value written as a character is not a Unicode scalar value. Carries the
+  /// value.
+  InvalidChar(i64),
+}
+
+impl OutputError {
+  /// Converts the error into an [`InterpretError`] reported at the given line.
+  #[inline]
+  pub(crate) fn at(self, line: usize) -> InterpretError {
+    match self {
+      OutputError::Io => InterpretError::IOError(line),
+      OutputError::InvalidChar(value) => InterpretError::InvalidChar(line, value),
+    }
+  }
+}
+
+impl core::fmt::Display for OutputError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      OutputError::Io => write!(f, "I/O error"),
+      OutputError::InvalidChar(value) => write!(f, "{value} is not a valid character"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OutputError {}
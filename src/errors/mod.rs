@@ -4,16 +4,40 @@
 //! This module includes the following error types:
 //! - [`ParseError`] for parsing errors that may occur during parsing and validating input.
 //! - [`InterpretError`] for interpretation errors that may occur during program execution.
+//! - [`InputError`] for errors reported by input sources.
+//! - [`OutputError`] for errors reported by output sinks.
+//! - [`SimError`] for errors reported by [`simulate`], wrapping parse and runtime errors.
 //!
 //! It also includes error-related types:
 //! - [`InvalidArgument`] for representing various invalid argument errors.
+//! - [`ParseErrorKind`] for grouping parse errors by their variant.
+//! - [`Span`] for pointing at the offending token inside a source line.
+//!
+//! With the `diagnostics` feature enabled, [`ParseError::to_diagnostic`] converts parse errors
+//! into `codespan_reporting` diagnostics.
 //!
 //! [`ParseError`]: enum.ParseError.html
 //! [`InterpretError`]: enum.InterpretError.html
+//! [`InputError`]: enum.InputError.html
+//! [`OutputError`]: enum.OutputError.html
+//! [`SimError`]: enum.SimError.html
+//! [`simulate`]: ../fn.simulate.html
 //! [`InvalidArgument`]: enum.InvalidArgument.html
+//! [`ParseErrorKind`]: enum.ParseErrorKind.html
+//! [`Span`]: struct.Span.html
 //! [`errors`]: errors/index.html
+#[cfg(feature = "diagnostics")]
+mod diagnostic;
+#[cfg(feature = "std")]
+mod io;
 mod parser;
 mod ram;
+#[cfg(feature = "std")]
+mod sim;
 
-pub use ram::*;
+#[cfg(feature = "std")]
+pub use io::*;
 pub use parser::*;
+pub use ram::*;
+#[cfg(feature = "std")]
+pub use sim::*;
@@ -1,62 +1,337 @@
-use std::error::Error;
+use alloc::string::String;
+use alloc::string::ToString;
+use core::cmp::Ordering;
+use core::ops::Range;
+
+/// Represents a byte range inside a source line, pointing at the token that caused an error.
+///
+/// Offsets are relative to the line passed to [`parse_line`], which [`parse`] trims before
+/// parsing.
+///
+/// [`parse_line`]: ../parser/fn.parse_line.html
+/// [`parse`]: ../parser/fn.parse.html
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub struct Span {
+  /// Byte offset of the first character of the token.
+  pub start: usize,
+  /// Byte offset right after the last character of the token.
+  pub end: usize,
+}
+
+impl Span {
+  /// Creates a new [`Span`] covering `start..end`.
+  #[inline]
+  pub fn new(start: usize, end: usize) -> Self {
+    Span { start, end }
+  }
+}
+
+impl From<Span> for Range<usize> {
+  #[inline]
+  fn from(span: Span) -> Self {
+    span.start..span.end
+  }
+}
 
 /// Represents various parsing errors that may occur during parsing and validating input.
-#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+///
+/// Every variant carries the line number first. Variants produced while parsing a line also
+/// carry the [`Span`] of the offending token.
+///
+/// Errors are ordered by line first, so sorting a list of errors puts them in source order.
+/// Errors on the same line are ordered by their [`ParseErrorKind`], then by span and by the
+/// remaining data.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum ParseError {
   /// Occurs when a label is not valid.
-  LabelIsNotValid(usize),
+  LabelIsNotValid(usize, Span),
+  /// Occurs when a label definition has no name, i.e. a lone `:`.
+  EmptyLabel(usize, Span),
 
   /// Occurs when unsupported syntax is encountered.
-  UnsupportedSyntax(usize),
+  UnsupportedSyntax(usize, Span),
   /// Occurs when an unsupported opcode is encountered.
-  UnsupportedOpcode(usize, String),
+  /// Carries the opcode as it was written in the source.
+  UnsupportedOpcode(usize, Span, String),
 
   /// Occurs when an argument is required but not provided.
-  ArgumentIsRequired(usize),
+  /// The span points at the opcode missing its argument.
+  ArgumentIsRequired(usize, Span),
   /// Occurs when an argument is not valid.
-  ArgumentIsNotValid(usize, InvalidArgument),
+  /// Carries the argument as written in the source last.
+  ArgumentIsNotValid(usize, Span, InvalidArgument, String),
+
+  /// Occurs when a jump refers to a label that is not defined in the program.
+  /// Carries the line of the jump and the missing label.
+  UnknownLabel(usize, String),
+  /// Occurs when a label is defined more than once.
+  /// Carries the line of the repeated definition and the label.
+  DuplicateLabel(usize, String),
+  /// Occurs when a label is named like an opcode, such as `HALT:`, and
+  /// [`ParserOptions::reject_reserved_label_names`] is enabled. Carries the label.
+  ///
+  /// [`ParserOptions::reject_reserved_label_names`]: ../parser/struct.ParserOptions.html#structfield.reject_reserved_label_names
+  ReservedLabelName(usize, Span, String),
+  /// Occurs when an alias defined with `ALIAS` refers back to itself through other aliases.
+  /// Carries the line and the name of the alias.
+  AliasCycle(usize, String),
+
+  /// Occurs when the source has more lines than allowed by [`ParseLimits::max_lines`].
+  /// Carries the first line past the limit.
+  ///
+  /// [`ParseLimits::max_lines`]: ../parser/struct.ParseLimits.html#structfield.max_lines
+  TooManyLines(usize),
+  /// Occurs when a line is longer than allowed by [`ParseLimits::max_line_len`].
+  /// Carries the line and its length in bytes.
+  ///
+  /// [`ParseLimits::max_line_len`]: ../parser/struct.ParseLimits.html#structfield.max_line_len
+  LineTooLong(usize, usize),
+  /// Occurs when expanding `REPEAT` blocks would produce more statements than allowed by
+  /// [`parse_with_repeats`]. Carries the line at which the limit was exceeded.
+  ///
+  /// [`parse_with_repeats`]: ../parser/fn.parse_with_repeats.html
+  TooManyStatements(usize),
+
+  /// Occurs when reading the source fails, see [`parse_reader`].
+  /// Carries the line that could not be read.
+  ///
+  /// [`parse_reader`]: ../parser/fn.parse_reader.html
+  Io(usize),
 
   /// Represents an unknown error that occurred at a specific index.
   UnknownError(usize),
 }
 
+/// Represents the kind of a [`ParseError`] without its associated data.
+///
+/// Useful for grouping errors, see [`ParseError::kind`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum ParseErrorKind {
+  LabelIsNotValid,
+  EmptyLabel,
+  UnsupportedSyntax,
+  UnsupportedOpcode,
+  ArgumentIsRequired,
+  ArgumentIsNotValid,
+  UnknownLabel,
+  DuplicateLabel,
+  ReservedLabelName,
+  AliasCycle,
+  TooManyLines,
+  LineTooLong,
+  TooManyStatements,
+  Io,
+  UnknownError,
+}
+
 /// Represents various invalid argument errors that may occur during parsing and validating input.
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
 pub enum InvalidArgument {
   LabelIsNotValid,
   ArgumentIsRequired,
-  ArgumentValueMustBeNumberic,
+  ArgumentValueMustBeNumeric,
+  /// The argument is a number that does not fit into its type, e.g. `=99999999999999999999`.
+  ArgumentValueOutOfRange,
   PureArgumentIsNotAllowed,
 
   ArgumentIsNotValid,
 }
 
+impl InvalidArgument {
+  /// The former misspelled name of [`InvalidArgument::ArgumentValueMustBeNumeric`].
+  #[deprecated(note = "use `InvalidArgument::ArgumentValueMustBeNumeric` instead")]
+  #[allow(non_upper_case_globals)]
+  pub const ArgumentValueMustBeNumberic: InvalidArgument =
+    InvalidArgument::ArgumentValueMustBeNumeric;
+}
+
+impl core::fmt::Display for InvalidArgument {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    let message = match self {
+      InvalidArgument::LabelIsNotValid => "label is not valid",
+      InvalidArgument::ArgumentIsRequired => "argument is required",
+      InvalidArgument::ArgumentValueMustBeNumeric => "argument value must be numeric",
+      InvalidArgument::ArgumentValueOutOfRange => "argument value is out of range",
+      InvalidArgument::PureArgumentIsNotAllowed => "pure argument is not allowed here",
+      InvalidArgument::ArgumentIsNotValid => "argument is not valid",
+    };
+    f.write_str(message)
+  }
+}
+
 impl ParseError {
+  /// Returns the line number at which the error occurred.
+  #[inline]
+  pub fn line(&self) -> usize {
+    match self {
+      ParseError::LabelIsNotValid(line, ..)
+      | ParseError::EmptyLabel(line, _)
+      | ParseError::UnsupportedSyntax(line, ..)
+      | ParseError::UnsupportedOpcode(line, ..)
+      | ParseError::ArgumentIsRequired(line, ..)
+      | ParseError::ArgumentIsNotValid(line, ..)
+      | ParseError::UnknownLabel(line, ..)
+      | ParseError::DuplicateLabel(line, ..)
+      | ParseError::ReservedLabelName(line, ..)
+      | ParseError::AliasCycle(line, _)
+      | ParseError::TooManyLines(line)
+      | ParseError::LineTooLong(line, _)
+      | ParseError::TooManyStatements(line)
+      | ParseError::Io(line)
+      | ParseError::UnknownError(line) => *line,
+    }
+  }
+
+  /// Returns the [`Span`] of the offending token, if the error points at one.
+  #[inline]
+  pub fn span(&self) -> Option<Span> {
+    match self {
+      ParseError::LabelIsNotValid(_, span)
+      | ParseError::EmptyLabel(_, span)
+      | ParseError::UnsupportedSyntax(_, span)
+      | ParseError::UnsupportedOpcode(_, span, _)
+      | ParseError::ArgumentIsRequired(_, span)
+      | ParseError::ArgumentIsNotValid(_, span, ..)
+      | ParseError::ReservedLabelName(_, span, _) => Some(*span),
+      ParseError::UnknownLabel(..)
+      | ParseError::DuplicateLabel(..)
+      | ParseError::AliasCycle(..)
+      | ParseError::TooManyLines(_)
+      | ParseError::LineTooLong(..)
+      | ParseError::TooManyStatements(_)
+      | ParseError::Io(_)
+      | ParseError::UnknownError(_) => None,
+    }
+  }
+
+  /// Returns the [`ParseErrorKind`] of the error.
+  #[inline]
+  pub fn kind(&self) -> ParseErrorKind {
+    match self {
+      ParseError::LabelIsNotValid(..) => ParseErrorKind::LabelIsNotValid,
+      ParseError::EmptyLabel(..) => ParseErrorKind::EmptyLabel,
+      ParseError::UnsupportedSyntax(..) => ParseErrorKind::UnsupportedSyntax,
+      ParseError::UnsupportedOpcode(..) => ParseErrorKind::UnsupportedOpcode,
+      ParseError::ArgumentIsRequired(..) => ParseErrorKind::ArgumentIsRequired,
+      ParseError::ArgumentIsNotValid(..) => ParseErrorKind::ArgumentIsNotValid,
+      ParseError::UnknownLabel(..) => ParseErrorKind::UnknownLabel,
+      ParseError::DuplicateLabel(..) => ParseErrorKind::DuplicateLabel,
+      ParseError::ReservedLabelName(..) => ParseErrorKind::ReservedLabelName,
+      ParseError::AliasCycle(..) => ParseErrorKind::AliasCycle,
+      ParseError::TooManyLines(_) => ParseErrorKind::TooManyLines,
+      ParseError::LineTooLong(..) => ParseErrorKind::LineTooLong,
+      ParseError::TooManyStatements(_) => ParseErrorKind::TooManyStatements,
+      ParseError::Io(_) => ParseErrorKind::Io,
+      ParseError::UnknownError(..) => ParseErrorKind::UnknownError,
+    }
+  }
+
+  /// Creates a new `ParseError` for an invalid argument of the given kind.
+  #[inline]
+  pub(crate) fn invalid_argument(
+    index: usize,
+    span: Span,
+    argument: InvalidArgument,
+    token: &str,
+  ) -> Self {
+    ParseError::ArgumentIsNotValid(index, span, argument, token.to_string())
+  }
+
   /// Creates a new `ParseError` for the `PureArgumentIsNotAllowed` case.
   #[inline]
-  pub(crate) fn pure_argument_not_allowed(index: usize) -> Self {
-    ParseError::ArgumentIsNotValid(index, InvalidArgument::PureArgumentIsNotAllowed)
+  pub(crate) fn pure_argument_not_allowed(index: usize, span: Span, token: &str) -> Self {
+    Self::invalid_argument(
+      index,
+      span,
+      InvalidArgument::PureArgumentIsNotAllowed,
+      token,
+    )
   }
 
   /// Creates a new `ParseError` for the `ArgumentIsNotValid` case.
   #[inline]
-  pub(crate) fn not_valid_argument(index: usize) -> Self {
-    ParseError::ArgumentIsNotValid(index, InvalidArgument::ArgumentIsNotValid)
+  pub(crate) fn not_valid_argument(index: usize, span: Span, token: &str) -> Self {
+    Self::invalid_argument(index, span, InvalidArgument::ArgumentIsNotValid, token)
   }
-  /// Creates a new `ParseError` for the `ArgumentValueMustBeNumberic` case.
+
+  /// Creates a new `ParseError` for the `ArgumentValueMustBeNumeric` case.
+  #[cfg(test)]
+  pub(crate) fn argument_value_must_be_numeric(index: usize, span: Span, token: &str) -> Self {
+    Self::invalid_argument(
+      index,
+      span,
+      InvalidArgument::ArgumentValueMustBeNumeric,
+      token,
+    )
+  }
+}
+
+impl Ord for ParseError {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self
+      .line()
+      .cmp(&other.line())
+      .then_with(|| self.kind().cmp(&other.kind()))
+      .then_with(|| self.span().cmp(&other.span()))
+      .then_with(|| match (self, other) {
+        (ParseError::UnsupportedOpcode(.., lhs), ParseError::UnsupportedOpcode(.., rhs))
+        | (ParseError::UnknownLabel(_, lhs), ParseError::UnknownLabel(_, rhs))
+        | (ParseError::DuplicateLabel(_, lhs), ParseError::DuplicateLabel(_, rhs))
+        | (ParseError::ReservedLabelName(.., lhs), ParseError::ReservedLabelName(.., rhs))
+        | (ParseError::AliasCycle(_, lhs), ParseError::AliasCycle(_, rhs)) => lhs.cmp(rhs),
+        (
+          ParseError::ArgumentIsNotValid(_, _, lhs, lhs_token),
+          ParseError::ArgumentIsNotValid(_, _, rhs, rhs_token),
+        ) => (lhs, lhs_token).cmp(&(rhs, rhs_token)),
+        (ParseError::LineTooLong(_, lhs), ParseError::LineTooLong(_, rhs)) => lhs.cmp(rhs),
+        _ => Ordering::Equal,
+      })
+  }
+}
+
+impl PartialOrd for ParseError {
   #[inline]
-  pub(crate) fn argument_value_must_be_numeric(index: usize) -> Self {
-    ParseError::ArgumentIsNotValid(index, InvalidArgument::ArgumentValueMustBeNumberic)
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
   }
 }
 
-impl std::fmt::Display for ParseError {
-  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-    write!(f, "Parse error")
+impl core::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+    match self {
+      ParseError::LabelIsNotValid(line, _) => write!(f, "line {line}: label is not valid"),
+      ParseError::EmptyLabel(line, _) => write!(f, "line {line}: label name is empty"),
+      ParseError::UnsupportedSyntax(line, _) => write!(f, "line {line}: unsupported syntax"),
+      ParseError::UnsupportedOpcode(line, _, opcode) => {
+        write!(f, "line {line}: unknown opcode '{opcode}'")
+      }
+      ParseError::ArgumentIsRequired(line, _) => write!(f, "line {line}: argument is required"),
+      ParseError::ArgumentIsNotValid(line, _, argument, token) => {
+        write!(f, "line {line}: {argument}, found '{token}'")
+      }
+      ParseError::UnknownLabel(line, label) => write!(f, "line {line}: unknown label '{label}'"),
+      ParseError::DuplicateLabel(line, label) => {
+        write!(f, "line {line}: duplicate label '{label}'")
+      }
+      ParseError::ReservedLabelName(line, _, label) => {
+        write!(f, "line {line}: label '{label}' is named like an opcode")
+      }
+      ParseError::AliasCycle(line, name) => {
+        write!(f, "line {line}: alias '{name}' refers to itself")
+      }
+      ParseError::TooManyLines(line) => write!(f, "line {line}: too many lines"),
+      ParseError::LineTooLong(line, len) => {
+        write!(f, "line {line}: line is too long ({len} bytes)")
+      }
+      ParseError::TooManyStatements(line) => write!(f, "line {line}: too many statements"),
+      ParseError::Io(line) => write!(f, "line {line}: I/O error"),
+      ParseError::UnknownError(line) => write!(f, "line {line}: unknown error"),
+    }
   }
 }
 
-impl Error for ParseError {}
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
 
 #[cfg(test)]
 mod tests {
@@ -68,7 +343,10 @@ mod tests {
     let line = "фывфыфыв:";
     let result = parse_line(line, 0);
 
-    assert_eq!(result, Err(ParseError::LabelIsNotValid(0)));
+    assert_eq!(
+      result,
+      Err(ParseError::LabelIsNotValid(0, Span::new(0, 17)))
+    );
   }
 
   #[test]
@@ -76,15 +354,25 @@ mod tests {
     let line = "LOAD 1 2";
     let result = parse_line(line, 0);
 
-    assert_eq!(result, Err(ParseError::UnsupportedSyntax(0)));
+    assert_eq!(
+      result,
+      Err(ParseError::UnsupportedSyntax(0, Span::new(7, 8)))
+    );
   }
 
   #[test]
   fn test_unsupported_opcode() {
-    let line = "KoKotinf 1 2";
-    let result = parse_line(line, 0);
-
-    assert_eq!(result, Err(ParseError::UnsupportedSyntax(0)));
+    // The opcode is checked before the number of arguments.
+    for line in ["KoKotinf", "KoKotinf 1", "KoKotinf 1 2"] {
+      assert_eq!(
+        parse_line(line, 0),
+        Err(ParseError::UnsupportedOpcode(
+          0,
+          Span::new(0, 8),
+          "KoKotinf".to_string()
+        ))
+      );
+    }
   }
 
   #[test]
@@ -92,7 +380,10 @@ mod tests {
     let line = "LOAD";
     let result = parse_line(line, 0);
 
-    assert_eq!(result, Err(ParseError::ArgumentIsRequired(0)));
+    assert_eq!(
+      result,
+      Err(ParseError::ArgumentIsRequired(0, Span::new(0, 4)))
+    );
   }
 
   #[test]
@@ -100,7 +391,14 @@ mod tests {
     let line = "STORE =1";
     let result = parse_line(line, 0);
 
-    assert_eq!(result, Err(ParseError::pure_argument_not_allowed(0)));
+    assert_eq!(
+      result,
+      Err(ParseError::pure_argument_not_allowed(
+        0,
+        Span::new(6, 8),
+        "=1"
+      ))
+    );
   }
 
   #[test]
@@ -108,7 +406,14 @@ mod tests {
     let line = "STORE *a";
     let result = parse_line(line, 0);
 
-    assert_eq!(result, Err(ParseError::argument_value_must_be_numeric(0)));
+    assert_eq!(
+      result,
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(6, 8),
+        "*a"
+      ))
+    );
   }
 
   #[test]
@@ -116,6 +421,198 @@ mod tests {
     let line = "STORE a";
     let result = parse_line(line, 0);
 
-    assert_eq!(result, Err(ParseError::not_valid_argument(0)));
+    assert_eq!(
+      result,
+      Err(ParseError::ArgumentIsNotValid(
+        0,
+        Span::new(6, 7),
+        InvalidArgument::ArgumentIsNotValid,
+        "a".to_string()
+      ))
+    );
+
+    let result = parse_line("LOAD xyz", 0);
+    assert_eq!(
+      result,
+      Err(ParseError::not_valid_argument(0, Span::new(5, 8), "xyz"))
+    );
+  }
+
+  #[test]
+  fn test_span_is_relative_to_the_line() {
+    let line = "loop: STORE   *a # comment";
+    let result = parse_line(line, 0);
+
+    assert_eq!(
+      result,
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(14, 16),
+        "*a"
+      ))
+    );
+    assert_eq!(Range::from(Span::new(14, 16)), 14..16);
+  }
+
+  #[test]
+  fn test_display() {
+    let message = |line| parse_line(line, 4).unwrap_err().to_string();
+
+    assert_eq!(message("FOO 1"), "line 4: unknown opcode 'FOO'");
+    assert_eq!(message("1abc:"), "line 4: label is not valid");
+    assert_eq!(message("LOAD 1 2"), "line 4: unsupported syntax");
+    assert_eq!(message("LOAD"), "line 4: argument is required");
+    assert_eq!(
+      message("STORE *a"),
+      "line 4: argument value must be numeric, found '*a'"
+    );
+    assert_eq!(
+      message("STORE =1"),
+      "line 4: pure argument is not allowed here, found '=1'"
+    );
+    assert_eq!(
+      message("STORE a"),
+      "line 4: argument is not valid, found 'a'"
+    );
+    assert_eq!(
+      message("LOAD xyz"),
+      "line 4: argument is not valid, found 'xyz'"
+    );
+    assert_eq!(
+      ParseError::UnknownLabel(7, "end".to_string()).to_string(),
+      "line 7: unknown label 'end'"
+    );
+    assert_eq!(
+      ParseError::ReservedLabelName(3, Span::new(0, 5), "HALT".to_string()).to_string(),
+      "line 3: label 'HALT' is named like an opcode"
+    );
+    assert_eq!(
+      ParseError::AliasCycle(5, "done".to_string()).to_string(),
+      "line 5: alias 'done' refers to itself"
+    );
+    assert_eq!(
+      ParseError::UnknownError(2).to_string(),
+      "line 2: unknown error"
+    );
+    assert_eq!(
+      ParseError::TooManyLines(4).to_string(),
+      "line 4: too many lines"
+    );
+    assert_eq!(
+      ParseError::LineTooLong(2, 9).to_string(),
+      "line 2: line is too long (9 bytes)"
+    );
+    assert_eq!(
+      ParseError::TooManyStatements(3).to_string(),
+      "line 3: too many statements"
+    );
+    assert_eq!(ParseError::Io(6).to_string(), "line 6: I/O error");
+  }
+
+  #[test]
+  fn test_span() {
+    assert_eq!(
+      ParseError::ArgumentIsRequired(1, Span::new(0, 4)).span(),
+      Some(Span::new(0, 4))
+    );
+    assert_eq!(ParseError::UnknownLabel(1, "end".to_string()).span(), None);
+  }
+
+  #[test]
+  fn test_line_and_kind() {
+    let span = Span::default();
+    let errors = [
+      (
+        ParseError::LabelIsNotValid(1, span),
+        ParseErrorKind::LabelIsNotValid,
+      ),
+      (
+        ParseError::UnsupportedSyntax(2, span),
+        ParseErrorKind::UnsupportedSyntax,
+      ),
+      (
+        ParseError::UnsupportedOpcode(3, span, "FOO".to_string()),
+        ParseErrorKind::UnsupportedOpcode,
+      ),
+      (
+        ParseError::ArgumentIsRequired(4, span),
+        ParseErrorKind::ArgumentIsRequired,
+      ),
+      (
+        ParseError::not_valid_argument(5, span, "a"),
+        ParseErrorKind::ArgumentIsNotValid,
+      ),
+      (
+        ParseError::UnknownLabel(6, "end".to_string()),
+        ParseErrorKind::UnknownLabel,
+      ),
+      (ParseError::UnknownError(7), ParseErrorKind::UnknownError),
+    ];
+
+    for (i, (error, kind)) in errors.iter().enumerate() {
+      assert_eq!(error.line(), i + 1);
+      assert_eq!(error.kind(), *kind);
+    }
+  }
+
+  #[test]
+  fn test_sort_by_line() {
+    let mut errors = [
+      ParseError::UnknownLabel(7, "end".to_string()),
+      ParseError::UnsupportedSyntax(2, Span::new(4, 6)),
+      ParseError::DuplicateLabel(5, "loop".to_string()),
+      ParseError::UnknownError(1),
+      ParseError::LabelIsNotValid(9, Span::new(0, 3)),
+      ParseError::UnsupportedOpcode(2, Span::new(0, 3), "FOO".to_string()),
+      ParseError::AliasCycle(3, "a".to_string()),
+    ];
+    errors.sort();
+
+    let lines: Vec<_> = errors.iter().map(ParseError::line).collect();
+    assert_eq!(lines, vec![1, 2, 2, 3, 5, 7, 9]);
+    // Errors on the same line are ordered by kind.
+    assert_eq!(errors[1].kind(), ParseErrorKind::UnsupportedSyntax);
+    assert_eq!(errors[2].kind(), ParseErrorKind::UnsupportedOpcode);
+
+    assert!(
+      ParseError::UnknownLabel(1, "a".to_string()) < ParseError::UnknownLabel(1, "b".to_string())
+    );
+  }
+
+  #[test]
+  fn test_invalid_argument_display() {
+    let messages = [
+      (InvalidArgument::LabelIsNotValid, "label is not valid"),
+      (InvalidArgument::ArgumentIsRequired, "argument is required"),
+      (
+        InvalidArgument::ArgumentValueMustBeNumeric,
+        "argument value must be numeric",
+      ),
+      (
+        InvalidArgument::ArgumentValueOutOfRange,
+        "argument value is out of range",
+      ),
+      (
+        InvalidArgument::PureArgumentIsNotAllowed,
+        "pure argument is not allowed here",
+      ),
+      (InvalidArgument::ArgumentIsNotValid, "argument is not valid"),
+    ];
+    for (argument, message) in messages {
+      assert_eq!(argument.to_string(), message);
+      assert_eq!(
+        ParseError::invalid_argument(2, Span::new(0, 1), argument, "x").to_string(),
+        format!("line 2: {message}, found 'x'")
+      );
+    }
+  }
+
+  #[test]
+  #[allow(deprecated)]
+  fn test_deprecated_names() {
+    assert_eq!(
+      InvalidArgument::ArgumentValueMustBeNumberic,
+      InvalidArgument::ArgumentValueMustBeNumeric
+    );
   }
 }
@@ -1,26 +1,189 @@
+use alloc::string::String;
+
 /// Represents various interpretation errors that may occur during program execution.
+///
+/// This is the runtime counterpart of [`ParseError`]: every variant carries the line of the
+/// instruction that failed as its first field.
+///
+/// [`ParseError`]: enum.ParseError.html
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InterpretError {
   /// Occurs when attempting to access memory outside the allowed range.
   SegmentationFault(usize),
   /// Occurs when a reference to an unknown label is encountered.
   UnknownLabel(usize),
+  /// Occurs when an indirect access goes through a register holding a negative number.
+  NegativeIndirectIndex(usize),
+  /// Occurs when an indirect jump targets an index outside the program.
+  InvalidJumpTarget(usize),
   /// Occurs when invalid input is provided during program execution.
   InvalidInput(usize, String),
+  /// Occurs when the program asks for input but the input is exhausted.
+  NoInput(usize),
   /// Occurs when an invalid literal value is encountered.
   InvalidLiteral(usize),
   /// Occurs when a division by zero is attempted.
   DivisionByZero(usize),
+  /// Occurs when the result of an arithmetic instruction does not fit into a register.
+  Overflow(usize),
+  /// Occurs when a register that was never written is read.
+  /// Carries the line and the index of the register.
+  UninitializedRegister(usize, usize),
+  /// Occurs when a register above the limit set with [`Ram::with_max_register`] is accessed.
+  /// Carries the line and the index of the register.
+  ///
+  /// [`Ram::with_max_register`]: ../ram/struct.Ram.html#method.with_max_register
+  RegisterIndexOutOfRange(usize, usize),
   /// Occurs when there is an error writing to provided writer.
   IOError(usize),
+  /// Occurs when a value written as a character is not a Unicode scalar value.
+  /// Carries the line and the value.
+  InvalidChar(usize, i64),
+  /// Occurs when execution reaches the end of the program without executing `HALT`.
+  FellOffEnd(usize),
   /// Occurs when the program is halted but step was made.
   Halted(usize),
+  /// Occurs when the program executed more instructions than allowed.
+  StepLimitExceeded(usize),
+  /// Occurs when the program writes more values than allowed by [`Ram::with_max_output`].
+  ///
+  /// [`Ram::with_max_output`]: ../ram/struct.Ram.html#method.with_max_output
+  OutputLimitExceeded(usize),
+  /// Occurs when the program runs longer than allowed by [`Ram::run_with_timeout`].
+  ///
+  /// [`Ram::run_with_timeout`]: ../ram/struct.Ram.html#method.run_with_timeout
+  Timeout(usize),
 }
 
-impl std::fmt::Display for InterpretError {
-  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    write!(f, "InterpretError")
+impl InterpretError {
+  /// Returns the line number of the instruction that caused the error.
+  #[inline]
+  pub fn line(&self) -> usize {
+    match self {
+      InterpretError::SegmentationFault(line)
+      | InterpretError::UnknownLabel(line)
+      | InterpretError::NegativeIndirectIndex(line)
+      | InterpretError::InvalidJumpTarget(line)
+      | InterpretError::InvalidInput(line, _)
+      | InterpretError::NoInput(line)
+      | InterpretError::InvalidLiteral(line)
+      | InterpretError::DivisionByZero(line)
+      | InterpretError::Overflow(line)
+      | InterpretError::UninitializedRegister(line, _)
+      | InterpretError::RegisterIndexOutOfRange(line, _)
+      | InterpretError::IOError(line)
+      | InterpretError::InvalidChar(line, _)
+      | InterpretError::FellOffEnd(line)
+      | InterpretError::Halted(line)
+      | InterpretError::StepLimitExceeded(line)
+      | InterpretError::OutputLimitExceeded(line)
+      | InterpretError::Timeout(line) => *line,
+    }
   }
 }
 
+impl core::fmt::Display for InterpretError {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      InterpretError::SegmentationFault(line) => write!(f, "line {line}: segmentation fault"),
+      InterpretError::UnknownLabel(line) => write!(f, "line {line}: unknown label"),
+      InterpretError::NegativeIndirectIndex(line) => {
+        write!(f, "line {line}: negative indirect register index")
+      }
+      InterpretError::InvalidJumpTarget(line) => write!(f, "line {line}: invalid jump target"),
+      InterpretError::InvalidInput(line, input) => {
+        write!(f, "line {line}: invalid input '{}'", input.trim())
+      }
+      InterpretError::NoInput(line) => write!(f, "line {line}: no input left"),
+      InterpretError::InvalidLiteral(line) => write!(f, "line {line}: invalid literal"),
+      InterpretError::DivisionByZero(line) => write!(f, "line {line}: division by zero"),
+      InterpretError::Overflow(line) => write!(f, "line {line}: arithmetic overflow"),
+      InterpretError::UninitializedRegister(line, index) => {
+        write!(f, "line {line}: register {index} is not initialized")
+      }
+      InterpretError::RegisterIndexOutOfRange(line, index) => {
+        write!(f, "line {line}: register {index} is out of range")
+      }
+      InterpretError::IOError(line) => write!(f, "line {line}: I/O error"),
+      InterpretError::InvalidChar(line, value) => {
+        write!(f, "line {line}: {value} is not a valid character")
+      }
+      InterpretError::FellOffEnd(line) => {
+        write!(
+          f,
+          "line {line}: reached the end of the program without HALT"
+        )
+      }
+      InterpretError::Halted(line) => write!(f, "line {line}: machine is halted"),
+      InterpretError::StepLimitExceeded(line) => write!(f, "line {line}: step limit exceeded"),
+      InterpretError::OutputLimitExceeded(line) => {
+        write!(f, "line {line}: output limit exceeded")
+      }
+      InterpretError::Timeout(line) => write!(f, "line {line}: timed out"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
 impl std::error::Error for InterpretError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_display() {
+    assert_eq!(
+      InterpretError::DivisionByZero(3).to_string(),
+      "line 3: division by zero"
+    );
+    assert_eq!(
+      InterpretError::InvalidInput(1, "abc\n".to_string()).to_string(),
+      "line 1: invalid input 'abc'"
+    );
+    assert_eq!(
+      InterpretError::UninitializedRegister(2, 5).to_string(),
+      "line 2: register 5 is not initialized"
+    );
+    assert_eq!(
+      InterpretError::RegisterIndexOutOfRange(3, 9).to_string(),
+      "line 3: register 9 is out of range"
+    );
+    assert_eq!(
+      InterpretError::InvalidJumpTarget(4).to_string(),
+      "line 4: invalid jump target"
+    );
+    assert_eq!(
+      InterpretError::NegativeIndirectIndex(3).to_string(),
+      "line 3: negative indirect register index"
+    );
+    assert_eq!(
+      InterpretError::NoInput(6).to_string(),
+      "line 6: no input left"
+    );
+    assert_eq!(
+      InterpretError::Overflow(5).to_string(),
+      "line 5: arithmetic overflow"
+    );
+    assert_eq!(
+      InterpretError::InvalidChar(2, -1).to_string(),
+      "line 2: -1 is not a valid character"
+    );
+    assert_eq!(
+      InterpretError::StepLimitExceeded(7).to_string(),
+      "line 7: step limit exceeded"
+    );
+    assert_eq!(
+      InterpretError::OutputLimitExceeded(2).to_string(),
+      "line 2: output limit exceeded"
+    );
+    assert_eq!(InterpretError::Timeout(4).to_string(), "line 4: timed out");
+  }
+
+  #[test]
+  fn test_line() {
+    assert_eq!(InterpretError::UninitializedRegister(2, 5).line(), 2);
+    assert_eq!(InterpretError::InvalidInput(8, String::new()).line(), 8);
+  }
+}
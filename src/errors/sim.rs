@@ -0,0 +1,72 @@
+use alloc::vec::Vec;
+
+use crate::errors::InterpretError;
+use crate::errors::ParseError;
+
+/// Represents errors reported by [`simulate`], either while parsing or while running the
+/// program.
+///
+/// [`simulate`]: ../fn.simulate.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimError {
+  /// Occurs when the program fails to parse. Carries every parse error, in source order.
+  Parse(Vec<ParseError>),
+  /// Occurs when the program fails while running.
+  Interpret(InterpretError),
+}
+
+impl From<Vec<ParseError>> for SimError {
+  #[inline]
+  fn from(errors: Vec<ParseError>) -> Self {
+    SimError::Parse(errors)
+  }
+}
+
+impl From<InterpretError> for SimError {
+  #[inline]
+  fn from(error: InterpretError) -> Self {
+    SimError::Interpret(error)
+  }
+}
+
+impl core::fmt::Display for SimError {
+  /// Formats parse errors one per line and the runtime error as [`InterpretError`] does.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      SimError::Parse(errors) => {
+        for (i, error) in errors.iter().enumerate() {
+          if i > 0 {
+            writeln!(f)?;
+          }
+          write!(f, "{error}")?;
+        }
+        Ok(())
+      }
+      SimError::Interpret(error) => write!(f, "{error}"),
+    }
+  }
+}
+
+impl std::error::Error for SimError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::string::ToString;
+
+  #[test]
+  fn test_display() {
+    let error = SimError::Parse(vec![
+      ParseError::UnknownLabel(1, "a".to_string()),
+      ParseError::UnknownLabel(3, "b".to_string()),
+    ]);
+    assert_eq!(
+      error.to_string(),
+      "line 1: unknown label 'a'\nline 3: unknown label 'b'"
+    );
+    assert_eq!(
+      SimError::from(InterpretError::NoInput(2)).to_string(),
+      "line 2: no input left"
+    );
+  }
+}
@@ -0,0 +1,365 @@
+//! The `io` module defines where the RAM machine takes its input values from and where
+//! it writes its output values to.
+//!
+//! The [`Input`] trait is consumed by the `INPUT`/`READ` instructions. It is implemented for
+//! every [`BufRead`], which reads one value per line, and for [`VecInput`], which is handy in
+//! tests. With the `stdin` feature enabled, [`StdinInput`] reads values from the standard input.
+//!
+//! The [`Output`] trait is fed by the `OUTPUT`/`WRITE` instructions. It is implemented for
+//! every [`Write`], which writes one value per line, and for [`VecOutput`], which records the
+//! values so they can be inspected without capturing the standard output. [`CharOutput`]
+//! renders the values as text instead.
+//!
+//! With [`Ram::with_char_io`] the machine reads and writes characters instead, through
+//! [`Input::next_char`] and [`Output::write_char`], using their code points as values.
+//!
+//! # Examples
+//!
+//! ```
+//! use ramemu::io::{Input, VecInput};
+//!
+//! let mut input = VecInput::from(vec![1, 2]);
+//!
+//! assert_eq!(input.next_input(), Ok(Some(1)));
+//! assert_eq!(input.next_input(), Ok(Some(2)));
+//! assert_eq!(input.next_input(), Ok(None));
+//! ```
+//!
+//! [`Ram::with_char_io`]: crate::ram::Ram::with_char_io
+use std::cell::RefCell;
+use std::io::BufRead;
+use std::io::Write;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use crate::errors::InputError;
+use crate::errors::OutputError;
+
+/// A source of values for the `INPUT`/`READ` instructions.
+pub trait Input {
+  /// Returns the next input value, or `None` once the input is exhausted.
+  fn next_input(&mut self) -> Result<Option<i64>, InputError>;
+
+  /// Returns the code point of the next character, or `None` once the input is exhausted.
+  /// Used instead of [`Input::next_input`] when the machine does character I/O, see
+  /// [`Ram::with_char_io`].
+  ///
+  /// By default the next value is taken as the code point.
+  ///
+  /// [`Ram::with_char_io`]: crate::ram::Ram::with_char_io
+  #[inline]
+  fn next_char(&mut self) -> Result<Option<i64>, InputError> {
+    self.next_input()
+  }
+}
+
+impl<R: BufRead> Input for R {
+  /// Reads the next line and parses it as a value.
+  fn next_input(&mut self) -> Result<Option<i64>, InputError> {
+    let mut input = String::new();
+    if self.read_line(&mut input).map_err(|_| InputError::Io)? == 0 {
+      return Ok(None);
+    }
+    input
+      .trim()
+      .parse()
+      .map(Some)
+      .map_err(|_| InputError::InvalidInput(input))
+  }
+
+  /// Reads the next UTF-8 encoded character.
+  fn next_char(&mut self) -> Result<Option<i64>, InputError> {
+    let mut bytes = [0; 4];
+    for len in 1..=bytes.len() {
+      if self
+        .read(&mut bytes[len - 1..len])
+        .map_err(|_| InputError::Io)?
+        == 0
+      {
+        return match len {
+          1 => Ok(None),
+          _ => Err(InputError::InvalidInput(
+            String::from_utf8_lossy(&bytes[..len - 1]).into_owned(),
+          )),
+        };
+      }
+      match std::str::from_utf8(&bytes[..len]) {
+        Ok(char) => return Ok(char.chars().next().map(|char| char as i64)),
+        // The character is not complete yet.
+        Err(error) if error.error_len().is_none() => {}
+        Err(_) => {
+          return Err(InputError::InvalidInput(
+            String::from_utf8_lossy(&bytes[..len]).into_owned(),
+          ))
+        }
+      }
+    }
+    Err(InputError::InvalidInput(
+      String::from_utf8_lossy(&bytes).into_owned(),
+    ))
+  }
+}
+
+/// An [`Input`] backed by a vector of values.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VecInput {
+  values: Vec<i64>,
+  position: usize,
+}
+
+impl VecInput {
+  /// Creates a new [`VecInput`] yielding the given values in order.
+  #[inline]
+  pub fn new(values: Vec<i64>) -> Self {
+    VecInput {
+      values,
+      position: 0,
+    }
+  }
+}
+
+impl FromStr for VecInput {
+  type Err = InputError;
+
+  /// Parses whitespace separated values, e.g. `"3 7\n-2"`.
+  ///
+  /// Fails with [`InputError::InvalidInput`] carrying the first token that is not a number.
+  fn from_str(input: &str) -> Result<Self, Self::Err> {
+    input
+      .split_whitespace()
+      .map(|token| {
+        token
+          .parse()
+          .map_err(|_| InputError::InvalidInput(token.to_string()))
+      })
+      .collect::<Result<Vec<i64>, _>>()
+      .map(VecInput::new)
+  }
+}
+
+impl From<Vec<i64>> for VecInput {
+  #[inline]
+  fn from(values: Vec<i64>) -> Self {
+    VecInput::new(values)
+  }
+}
+
+impl From<&[i64]> for VecInput {
+  #[inline]
+  fn from(values: &[i64]) -> Self {
+    VecInput::new(values.to_vec())
+  }
+}
+
+impl Input for VecInput {
+  #[inline]
+  fn next_input(&mut self) -> Result<Option<i64>, InputError> {
+    let value = self.values.get(self.position).copied();
+    self.position += value.is_some() as usize;
+    Ok(value)
+  }
+}
+
+/// An [`Input`] reading one value per line from the standard input.
+#[cfg(feature = "stdin")]
+pub struct StdinInput(std::io::StdinLock<'static>);
+
+#[cfg(feature = "stdin")]
+impl Default for StdinInput {
+  #[inline]
+  fn default() -> Self {
+    StdinInput(std::io::stdin().lock())
+  }
+}
+
+#[cfg(feature = "stdin")]
+impl Input for StdinInput {
+  #[inline]
+  fn next_input(&mut self) -> Result<Option<i64>, InputError> {
+    self.0.next_input()
+  }
+}
+
+/// A sink for the values written by the `OUTPUT`/`WRITE` instructions.
+///
+/// The method is named `write_output` rather than `write` so it does not clash with
+/// [`Write::write`] on types implementing both traits.
+pub trait Output {
+  /// Writes a single value.
+  fn write_output(&mut self, value: i64) -> Result<(), OutputError>;
+
+  /// Writes the character with the code point `value`. Used instead of
+  /// [`Output::write_output`] when the machine does character I/O, see [`Ram::with_char_io`].
+  ///
+  /// By default the code point is validated and written as a value. Fails with
+  /// [`OutputError::InvalidChar`] if `value` is not a Unicode scalar value.
+  ///
+  /// [`Ram::with_char_io`]: crate::ram::Ram::with_char_io
+  #[inline]
+  fn write_char(&mut self, value: i64) -> Result<(), OutputError> {
+    to_char(value)?;
+    self.write_output(value)
+  }
+}
+
+impl<W: Write> Output for W {
+  /// Writes the value followed by a newline.
+  #[inline]
+  fn write_output(&mut self, value: i64) -> Result<(), OutputError> {
+    writeln!(self, "{}", value).map_err(|_| OutputError::Io)
+  }
+
+  /// Writes the UTF-8 encoded character, without a newline.
+  #[inline]
+  fn write_char(&mut self, value: i64) -> Result<(), OutputError> {
+    write!(self, "{}", to_char(value)?).map_err(|_| OutputError::Io)
+  }
+}
+
+/// Converts a code point into a [`char`], failing with [`OutputError::InvalidChar`].
+#[inline]
+fn to_char(value: i64) -> Result<char, OutputError> {
+  u32::try_from(value)
+    .ok()
+    .and_then(char::from_u32)
+    .ok_or(OutputError::InvalidChar(value))
+}
+
+/// An [`Output`] recording every written value.
+///
+/// Clones share the same storage, so a clone can be handed to the machine while the
+/// original is kept to inspect the values afterwards.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::io::{Output, VecOutput};
+///
+/// let output = VecOutput::default();
+/// let mut sink = output.clone();
+/// sink.write_output(4).unwrap();
+///
+/// assert_eq!(output.values(), vec![4]);
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct VecOutput {
+  values: Rc<RefCell<Vec<i64>>>,
+}
+
+impl VecOutput {
+  /// Returns the values written so far.
+  #[inline]
+  pub fn values(&self) -> Vec<i64> {
+    self.values.borrow().clone()
+  }
+
+  /// Discards the values written so far, e.g. before reusing a machine with [`Ram::reset`].
+  ///
+  /// [`Ram::reset`]: crate::ram::Ram::reset
+  #[inline]
+  pub fn clear(&self) {
+    self.values.borrow_mut().clear();
+  }
+}
+
+impl Output for VecOutput {
+  #[inline]
+  fn write_output(&mut self, value: i64) -> Result<(), OutputError> {
+    self.values.borrow_mut().push(value);
+    Ok(())
+  }
+}
+
+/// An [`Output`] interpreting every written value as the code point of a character and
+/// accumulating the text.
+///
+/// Writing a value that is not a Unicode scalar value fails with
+/// [`OutputError::InvalidChar`]. Like [`VecOutput`], clones share the same storage.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::io::{CharOutput, Output};
+///
+/// let output = CharOutput::default();
+/// let mut sink = output.clone();
+/// sink.write_output(72).unwrap();
+/// sink.write_output(73).unwrap();
+///
+/// assert_eq!(output.text(), "HI");
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct CharOutput {
+  text: Rc<RefCell<String>>,
+}
+
+impl CharOutput {
+  /// Returns the text written so far.
+  #[inline]
+  pub fn text(&self) -> String {
+    self.text.borrow().clone()
+  }
+}
+
+impl Output for CharOutput {
+  #[inline]
+  fn write_output(&mut self, value: i64) -> Result<(), OutputError> {
+    self.text.borrow_mut().push(to_char(value)?);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::io::Cursor;
+
+  use super::*;
+
+  #[test]
+  fn test_next_char() {
+    let mut input = Cursor::new("aé\n€".as_bytes());
+    let chars: Vec<_> = std::iter::from_fn(|| input.next_char().unwrap()).collect();
+    assert_eq!(chars, vec!['a' as i64, 'é' as i64, '\n' as i64, '€' as i64]);
+
+    let mut input = Cursor::new(&[0xE2, 0x82][..]);
+    assert!(matches!(
+      input.next_char(),
+      Err(InputError::InvalidInput(_))
+    ));
+
+    let mut input = Cursor::new(&[0xFF][..]);
+    assert_eq!(
+      input.next_char(),
+      Err(InputError::InvalidInput("\u{FFFD}".to_string()))
+    );
+    let mut input = Cursor::new(&[0xE2, b'A'][..]);
+    assert_eq!(
+      input.next_char(),
+      Err(InputError::InvalidInput("\u{FFFD}A".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_write_char() {
+    let mut output = Vec::new();
+    output.write_char('h' as i64).unwrap();
+    output.write_char('€' as i64).unwrap();
+    assert_eq!(output, "h€".as_bytes());
+
+    assert_eq!(output.write_char(-1), Err(OutputError::InvalidChar(-1)));
+    assert_eq!(
+      VecOutput::default().write_char(0xD800),
+      Err(OutputError::InvalidChar(0xD800))
+    );
+  }
+
+  #[test]
+  fn test_char_output() {
+    let output = CharOutput::default();
+    let mut sink = output.clone();
+    sink.write_output(72).unwrap();
+    sink.write_char(73).unwrap();
+    assert_eq!(sink.write_output(-1), Err(OutputError::InvalidChar(-1)));
+    assert_eq!(output.text(), "HI");
+  }
+}
@@ -4,32 +4,62 @@
 //!
 //! The library is organized into the following modules:
 //!
+//! - [`compiled`] for a flat representation of programs with resolved jump targets.
 //! - [`errors`] for error types related to parsing and interpretation.
+//! - [`io`] for the input sources and output sinks used by the RAM machine.
+//! - [`optimize`] for optimization passes that preserve the behavior of a program.
 //! - [`parser`] for parsing assembly code into an intermediate representation.
 //! - [`program`] for representing and working with a program in memory.
 //! - [`ram`] for the RAM machine implementation and its execution logic.
 //! - [`registers`] for working with the RAM machine registers.
 //! - [`stmt`] for representing and working with assembly statements.
 //!
+//! For the common case of running a program on some input and collecting its output,
+//! [`simulate`] does it in a single call.
+//!
 //! Additionally, the library will provide the following optional features:
 //!
+//! - `std` (enabled by default): Adds the [`compiled`], [`io`], [`optimize`], [`program`],
+//!   [`ram`] and [`registers`] modules, the [`simulate`] function, reading source code from a `BufRead` with
+//!   [`parser::parse_reader`], and `std::error::Error` implementations for the error types.
+//!   Without it, the parser, the statements and the errors only depend on `core` and `alloc`.
+//! - `stdin`: Adds an input source reading from the standard input.
 //! - `wasm`: Adds WebAssembly bindings for using the library in a WebAssembly environment.
-//! - `serde`: Adds serialization and deserialization support for the RAM machine state.
+//! - `serde`: Adds serialization and deserialization support for statements, programs and
+//!   snapshots of the RAM machine state.
+//! - `diagnostics`: Converts parse errors into `codespan-reporting` diagnostics.
 //!
+//! [`compiled`]: compiled/index.html
 //! [`errors`]: errors/index.html
+//! [`io`]: io/index.html
+//! [`optimize`]: optimize/index.html
 //! [`parser`]: parser/index.html
 //! [`program`]: program/index.html
 //! [`ram`]: ram/index.html
 //! [`registers`]: registers/index.html
 //! [`stmt`]: stmt/index.html
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod compiled;
 pub mod errors;
+#[cfg(feature = "std")]
+pub mod io;
+#[cfg(feature = "std")]
+pub mod optimize;
 pub mod parser;
+#[cfg(feature = "std")]
 pub mod program;
+#[cfg(feature = "std")]
 pub mod ram;
+#[cfg(feature = "std")]
 pub mod registers;
 pub mod stmt;
 
 #[cfg(feature = "wasm")]
 pub mod wasm_bindings;
 
-// TODO: Serde feature
+#[cfg(feature = "std")]
+pub use ram::simulate;
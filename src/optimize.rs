@@ -0,0 +1,247 @@
+//! The `optimize` module provides passes that rewrite a [`Program`] into a smaller one with the
+//! same behavior, e.g. to show how a program could be optimized.
+//!
+//! [`Program`]: ../program/struct.Program.html
+
+use crate::{
+  program::Program,
+  stmt::{Stmt, Value},
+};
+
+/// Returns an optimized copy of the program, which behaves the same when run.
+///
+/// Arithmetic with a pure value that directly follows a `LOAD` of a pure value is folded into
+/// that `LOAD`, so `LOAD =2` followed by `ADD =3` becomes `LOAD =5`. Folding stops at any other
+/// statement, like a label, a jump or an output, and at an operation that would fail or
+/// overflow, so the result doesn't depend on the policies of the machine. An indirect jump may
+/// land on any instruction and an unresolved label address depends on the indices of the
+/// instructions, so a program containing either is returned unchanged.
+///
+/// The optimized program executes fewer instructions, which shows in step counts and traces.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::{optimize::optimize, program::Program, stmt::{Stmt, Value}};
+///
+/// let program = Program::from_source("LOAD =2\nADD =3\nMUL =4\nWRITE 0\nHALT").unwrap();
+/// let optimized = optimize(&program);
+///
+/// assert_eq!(optimized.get(0), Some(&Stmt::Load(Value::Pure(20), 1)));
+/// assert_eq!(optimized.instructions.len(), 3);
+/// ```
+pub fn optimize(program: &Program) -> Program {
+  if program
+    .iter()
+    .any(|stmt| matches!(stmt, Stmt::JumpIndirect(..)) || has_label_address(stmt))
+  {
+    return program.clone();
+  }
+
+  let mut instructions: Vec<Stmt> = Vec::with_capacity(program.instructions.len());
+  for stmt in program {
+    if let Some(Stmt::Load(Value::Pure(accumulator), _)) = instructions.last_mut() {
+      if let Some(result) = fold(*accumulator, stmt) {
+        *accumulator = result;
+        continue;
+      }
+    }
+    instructions.push(stmt.clone());
+  }
+
+  Program::from(instructions)
+}
+
+/// Returns a copy of the program without the statements that can never be executed, as found
+/// by [`Program::unreachable_statements`].
+///
+/// Label and alias definitions are kept even when unreachable, so every jump still has its
+/// target. Since unreachable statements never run, the program behaves the same when run.
+/// Removing statements would change the value of an unresolved label address, so a program
+/// containing one is returned unchanged.
+///
+/// [`Program::unreachable_statements`]: ../program/struct.Program.html#method.unreachable_statements
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::{optimize::eliminate_dead_code, program::Program};
+///
+/// let program = Program::from_source("JUMP end\nWRITE =1\nend: HALT").unwrap();
+/// let program = eliminate_dead_code(&program);
+///
+/// assert_eq!(program.instructions.len(), 3);
+/// ```
+pub fn eliminate_dead_code(program: &Program) -> Program {
+  if program.iter().any(has_label_address) {
+    return program.clone();
+  }
+
+  let unreachable = program.unreachable_statements();
+  let instructions = program
+    .iter()
+    .enumerate()
+    .filter(|(index, stmt)| {
+      matches!(stmt, Stmt::Label(..) | Stmt::Alias(..)) || unreachable.binary_search(index).is_err()
+    })
+    .map(|(_, stmt)| stmt.clone())
+    .collect();
+
+  Program::from(instructions)
+}
+
+/// Returns whether the statement has a label address (`=@name`) that is not resolved yet.
+fn has_label_address(stmt: &Stmt) -> bool {
+  matches!(
+    stmt,
+    Stmt::Load(Value::LabelAddr(_), _)
+      | Stmt::Add(Value::LabelAddr(_), _)
+      | Stmt::Sub(Value::LabelAddr(_), _)
+      | Stmt::Mul(Value::LabelAddr(_), _)
+      | Stmt::Div(Value::LabelAddr(_), _)
+      | Stmt::Mod(Value::LabelAddr(_), _)
+      | Stmt::Pow(Value::LabelAddr(_), _)
+      | Stmt::Output(Value::LabelAddr(_), _)
+  )
+}
+
+/// Applies an arithmetic statement with a pure value to a known accumulator. Returns `None`
+/// for any other statement and when the operation fails or overflows.
+fn fold(accumulator: isize, stmt: &Stmt) -> Option<isize> {
+  match stmt {
+    Stmt::Add(Value::Pure(value), _) => accumulator.checked_add(*value),
+    Stmt::Sub(Value::Pure(value), _) => accumulator.checked_sub(*value),
+    Stmt::Mul(Value::Pure(value), _) => accumulator.checked_mul(*value),
+    Stmt::Div(Value::Pure(value), _) => accumulator.checked_div(*value),
+    Stmt::Mod(Value::Pure(value), _) => accumulator.checked_rem(*value),
+    Stmt::Pow(Value::Pure(value), _) => accumulator.checked_pow(u32::try_from(*value).ok()?),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::errors::InterpretError;
+  use crate::io::{VecInput, VecOutput};
+  use crate::ram::Ram;
+  use crate::stmt::{Label, RegisterValue};
+
+  /// Runs the program and returns the outcome, the written values and the accumulator.
+  fn run(program: Program, input: Vec<i64>) -> (Result<(), InterpretError>, Vec<i64>, i64) {
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      program,
+      Box::new(VecInput::from(input)),
+      Box::new(output.clone()),
+    );
+    let result = ram.run();
+    (result, output.values(), ram.accumulator())
+  }
+
+  fn assert_same_behavior(source: &str, input: Vec<i64>) -> Program {
+    let program = Program::from_source(source).unwrap();
+    let optimized = optimize(&program);
+    assert_eq!(
+      run(optimized.clone(), input.clone()),
+      run(program, input),
+      "{source}"
+    );
+    optimized
+  }
+
+  #[test]
+  fn test_folds_arithmetic() {
+    let optimized = assert_same_behavior(
+      "LOAD =2\nADD =3\nSUB =1\nMUL =6\nDIV =4\nMOD =4\nPOW =3\nWRITE 0\nHALT",
+      vec![],
+    );
+    assert_eq!(
+      optimized.instructions,
+      vec![
+        Stmt::Load(Value::Pure(8), 1),
+        Stmt::Output(Value::Register(RegisterValue::Direct(0)), 8),
+        Stmt::Halt(9),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_stops_at_labels_and_registers() {
+    let source = "LOAD =0\nloop: ADD =1\nWRITE 0\nSUB =3\nJZ end\nADD =3\nJUMP loop\nend: LOAD =3\nADD 0\nHALT";
+    let optimized = assert_same_behavior(source, vec![]);
+    assert_eq!(
+      optimized.instructions,
+      Program::from_source(source).unwrap().instructions
+    );
+
+    let optimized = assert_same_behavior("READ 1\nLOAD =4\nADD 1\nADD =2\nWRITE 0\nHALT", vec![3]);
+    assert_eq!(optimized.instructions.len(), 6);
+  }
+
+  #[test]
+  fn test_keeps_failing_operations() {
+    for source in [
+      "LOAD =1\nDIV =0\nHALT",
+      "LOAD =1\nMOD =0\nHALT",
+      "LOAD =2\nPOW =-1\nHALT",
+      "LOAD =9223372036854775807\nADD =1\nHALT",
+    ] {
+      let optimized = assert_same_behavior(source, vec![]);
+      assert_eq!(optimized.instructions.len(), 3, "{source}");
+    }
+  }
+
+  #[test]
+  fn test_indirect_jump_disables_folding() {
+    let source = "LOAD =3\nADD =1\nJIND 0\nHALT\nHALT";
+    let optimized = assert_same_behavior(source, vec![]);
+    assert_eq!(optimized.instructions.len(), 5);
+  }
+
+  #[test]
+  fn test_eliminate_dead_code() {
+    let source = "READ 1\nLOAD 1\nJZ zero\nWRITE =1\nJUMP end\nWRITE =99\nLOAD =5\nzero: WRITE =0\nend: HALT\ndead: WRITE =7\nJUMP dead";
+    let program = Program::from_source(source).unwrap();
+    let eliminated = eliminate_dead_code(&program);
+
+    let expected = Program::from_source(
+      "READ 1\nLOAD 1\nJZ zero\nWRITE =1\nJUMP end\nzero: WRITE =0\nend: HALT\ndead:",
+    )
+    .unwrap();
+    assert_eq!(eliminated.instructions.len(), expected.instructions.len());
+    for (stmt, expected) in eliminated.iter().zip(&expected) {
+      assert!(stmt.eq_ignoring_line(expected), "{stmt} != {expected}");
+    }
+    // Only the definition of the label `dead` is left unreachable.
+    assert_eq!(eliminated.unreachable_statements(), vec![9]);
+    for input in [0, 3] {
+      assert_eq!(
+        run(eliminated.clone(), vec![input]),
+        run(program.clone(), vec![input])
+      );
+    }
+  }
+
+  #[test]
+  fn test_label_addresses() {
+    // Resolved addresses are plain numbers, so folding keeps the written address.
+    let source = "LOAD =2\nADD =3\nWRITE =@end\nJUMP end\nWRITE =9\nend: HALT";
+    let optimized = assert_same_behavior(source, vec![]);
+    assert_eq!(optimized.instructions.len(), 6);
+    let program = Program::from_source(source).unwrap();
+    assert_eq!(
+      run(eliminate_dead_code(&program), vec![]),
+      run(program, vec![])
+    );
+
+    // An unresolved address would change once statements are removed.
+    let mut program = Program::from_source(source).unwrap();
+    program.instructions[2] = Stmt::Output(Value::LabelAddr(Label::new("end".to_string())), 3);
+    assert_eq!(optimize(&program).instructions, program.instructions);
+    assert_eq!(
+      eliminate_dead_code(&program).instructions,
+      program.instructions
+    );
+  }
+}
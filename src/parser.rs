@@ -1,126 +1,1022 @@
 //! The `parser` module is responsible for parsing the source code of the
 //! assembly language into a statements. It provides methods for parsing
-//! individual lines of source code as well as entire programs.
+//! individual lines of source code as well as entire programs, and for
+//! re-emitting a program in a canonical format.
 //!
 
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::num::IntErrorKind;
+use core::ops::Range;
+#[cfg(feature = "std")]
+use std::io::BufRead;
+
+use crate::errors::InvalidArgument;
 use crate::errors::ParseError;
+use crate::errors::Span;
 
 use crate::stmt::Label;
 use crate::stmt::RegisterValue;
 use crate::stmt::Stmt;
 use crate::stmt::Value;
 
+/// The characters starting a comment unless configured otherwise, see
+/// [`ParserOptions::comment_chars`].
+const DEFAULT_COMMENT_CHARS: &[char] = &['#'];
+
+/// Options controlling how source code is parsed.
+///
+/// The default options keep the parser's standard behavior.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParserOptions {
+  /// Treats labels differing only in case, such as `Loop` and `loop`, as the same label by
+  /// lowercasing both label definitions and jump targets. Defaults to `false`.
+  pub case_insensitive_labels: bool,
+  /// Allows non-ASCII letters and digits in labels, so labels can be written in any
+  /// language. A label must then start with a character of the Unicode `Alphabetic` property
+  /// or `_`, followed by characters that are `Alphabetic`, `Numeric` or `_` (see
+  /// [`char::is_alphabetic`] and [`char::is_alphanumeric`]). Defaults to `false`, which only
+  /// accepts ASCII letters, digits and `_`.
+  pub unicode_labels: bool,
+  /// The characters starting a comment that extends to the end of the line. Defaults to `#`.
+  ///
+  /// Comments are stripped before the line is split into `;` separated statements, so adding
+  /// `;` here turns it into a comment delimiter and disables it as a statement separator.
+  pub comment_chars: Vec<char>,
+  /// Rejects labels named like an opcode, such as `HALT:`, with
+  /// [`ParseError::ReservedLabelName`]. Defaults to `false`, which accepts them;
+  /// [`parse_program`] reports them as [`ParseOutcome::warnings`] instead.
+  pub reject_reserved_label_names: bool,
+  /// Additional mnemonics, mapped to the opcode they stand for, e.g. `PRINT` to `OUTPUT`.
+  /// Both are matched case-insensitively, and an alias takes precedence over an opcode of
+  /// the same name. An alias of an unknown opcode fails with
+  /// [`ParseError::UnsupportedOpcode`]. Defaults to no aliases.
+  pub opcode_aliases: BTreeMap<String, String>,
+}
+
+impl Default for ParserOptions {
+  #[inline]
+  fn default() -> Self {
+    ParserOptions {
+      case_insensitive_labels: false,
+      unicode_labels: false,
+      comment_chars: DEFAULT_COMMENT_CHARS.to_vec(),
+      reject_reserved_label_names: false,
+      opcode_aliases: BTreeMap::new(),
+    }
+  }
+}
+
+impl ParserOptions {
+  /// Returns the label name as stored in the parsed statements.
+  #[inline]
+  fn label(&self, label: &str) -> String {
+    if self.case_insensitive_labels {
+      label.to_lowercase()
+    } else {
+      label.to_string()
+    }
+  }
+
+  /// Returns the uppercased opcode the mnemonic stands for.
+  #[inline]
+  fn opcode(&self, mnemonic: &str) -> String {
+    self
+      .opcode_aliases
+      .iter()
+      .find(|(alias, _)| alias.eq_ignore_ascii_case(mnemonic))
+      .map_or(mnemonic, |(_, opcode)| opcode.as_str())
+      .to_uppercase()
+  }
+
+  /// Reports a [`ParseError::ReservedLabelName`] if `label` is named like an opcode: as an
+  /// error if reserved label names are rejected, otherwise as one of the `warnings`.
+  #[inline]
+  fn check_reserved(
+    &self,
+    label: &str,
+    line: usize,
+    span: Span,
+    warnings: &mut Vec<ParseError>,
+  ) -> Result<(), ParseError> {
+    if OPCODES.contains(&label.to_uppercase().as_str()) {
+      let error = ParseError::ReservedLabelName(line, span, label.to_string());
+      if self.reject_reserved_label_names {
+        return Err(error);
+      }
+      warnings.push(error);
+    }
+    Ok(())
+  }
+}
+
+/// The result of parsing a whole program with [`parse_program`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ParseOutcome {
+  /// The successfully parsed statements, in source order.
+  pub statements: Vec<Stmt>,
+  /// The errors of the lines that failed to parse, in source order.
+  pub errors: Vec<ParseError>,
+  /// Problems that don't prevent the program from parsing, in source order. Currently
+  /// [`ParseError::ReservedLabelName`] for labels named like an opcode.
+  pub warnings: Vec<ParseError>,
+}
+
+impl ParseOutcome {
+  /// Returns `true` if the program parsed without errors.
+  #[inline]
+  pub fn is_ok(&self) -> bool {
+    self.errors.is_empty()
+  }
+}
+
+/// Maps line numbers of several concatenated sources back to the source they come from.
+///
+/// Every source added to the map gets the lines following the sources added before it, with
+/// the first source starting at line `1`. Parse every source with [`parse_with_line_offset`]
+/// and the line returned by [`SourceMap::add`] to get line numbers the map understands.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::{parse_with_line_offset, SourceMap};
+///
+/// let mut map = SourceMap::default();
+/// let main = "LOAD =1\nJUMP lib";
+/// let lib = "lib: FOO";
+///
+/// assert_eq!(parse_with_line_offset(main, map.add("main.ram", main)).count(), 2);
+/// let error = parse_with_line_offset(lib, map.add("lib.ram", lib))
+///   .find_map(Result::err)
+///   .unwrap();
+///
+/// assert_eq!(error.line(), 3);
+/// assert_eq!(map.lookup(error.line()), Some(("lib.ram", 1)));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SourceMap {
+  /// The name of every source with the range of global lines it occupies.
+  sources: Vec<(String, Range<usize>)>,
+}
+
+impl SourceMap {
+  /// Adds a source placed after the sources added so far and returns the global line number
+  /// of its first line.
+  pub fn add(&mut self, name: &str, source: &str) -> usize {
+    let start = self.sources.last().map_or(1, |(_, lines)| lines.end);
+    let lines = start..start + source.lines().count();
+    self.sources.push((name.to_string(), lines));
+    start
+  }
+
+  /// Returns the name of the source holding the global line and the line number within that
+  /// source, starting at `1`. Returns `None` for a line outside of every source.
+  pub fn lookup(&self, line: usize) -> Option<(&str, usize)> {
+    self
+      .sources
+      .iter()
+      .find(|(_, lines)| lines.contains(&line))
+      .map(|(name, lines)| (name.as_str(), line - lines.start + 1))
+  }
+}
+
+/// How a line was split into tokens, as returned by [`try_parse_line`].
+///
+/// The spans describe the first statement of the line, i.e. everything before the first `;`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LineLex {
+  /// The span of the label definition including its `:`, if any.
+  pub label: Option<Span>,
+  /// The span of the opcode, if any.
+  pub opcode: Option<Span>,
+  /// The span from the first to the last argument of the opcode, if any.
+  pub argument: Option<Span>,
+  /// The span of the comment including its `#`, if any.
+  pub comment: Option<Span>,
+  /// The number of whitespace separated tokens of the first statement.
+  pub tokens: usize,
+}
+
 /// Parses the source code and returns an iterator over [`Result<Stmt, ParseError>`].
 ///
-/// This function processes each line of the source code, parsing it into a [`Stmt`] or
+/// This function processes each line of the source code, parsing it into [`Stmt`]s or
 /// a [`ParseError`] if an error occurs. It skips empty lines and comments.
 pub fn parse(source: &str) -> impl Iterator<Item = Result<Stmt, ParseError>> + '_ {
+  parse_with_options(source, ParserOptions::default())
+}
+
+/// Parses the source code like [`parse`], using the given [`ParserOptions`].
+pub fn parse_with_options(
+  source: &str,
+  options: ParserOptions,
+) -> impl Iterator<Item = Result<Stmt, ParseError>> + '_ {
+  parse_lines(source, 1, options)
+}
+
+/// Parses the source code like [`parse`], numbering its first line `start_line` instead of
+/// `1`.
+///
+/// This keeps line numbers absolute when parsing a snippet of a larger program, e.g. one of
+/// several concatenated files. A [`SourceMap`] maps such line numbers back to their file.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::parse_with_line_offset;
+/// use ramemu::stmt::Stmt;
+///
+/// let stmts: Vec<_> = parse_with_line_offset("NOP\nHALT", 10).collect();
+/// assert_eq!(stmts, vec![Ok(Stmt::Nop(10)), Ok(Stmt::Halt(11))]);
+/// ```
+pub fn parse_with_line_offset(
+  source: &str,
+  start_line: usize,
+) -> impl Iterator<Item = Result<Stmt, ParseError>> + '_ {
+  parse_lines(source, start_line, ParserOptions::default())
+}
+
+/// Parses every line of the source code, numbering the first one `start_line`.
+fn parse_lines(
+  source: &str,
+  start_line: usize,
+  options: ParserOptions,
+) -> impl Iterator<Item = Result<Stmt, ParseError>> + '_ {
   source
     .lines()
     .enumerate()
-    .map(|(i, l)| (i + 1, l.trim()))
-    .map(|(i, l)| parse_line(l, i))
-    .filter_map(|result| result.transpose())
+    .map(move |(i, l)| (start_line + i, l.trim()))
+    .flat_map(
+      move |(i, l)| match parse_line_with_options(l, i, &options) {
+        Ok(stmts) => stmts.into_iter().map(Ok).collect(),
+        Err(error) => vec![Err(error)],
+      },
+    )
 }
 
-/// Parses a single line of source code and returns a [`Result`] containing an [`Option<Stmt>`]
-/// or a [`ParseError`].
+/// Parses the source code like [`parse`], pairing every statement with its byte range in
+/// `source`.
 ///
-/// This function processes a single line of source code, returning `None` for empty lines
-/// or lines containing only comments. If the line contains an instruction or label, it returns
-/// a [`Stmt`] wrapped in a `Some`. In case of a parsing error, it returns a [`ParseError`]
-pub fn parse_line(source: &str, line: usize) -> Result<Option<Stmt>, ParseError> {
-  let facts: Vec<_> = source
-    .split('#')
-    .next()
-    .unwrap_or("")
-    .split_whitespace()
+/// The range of a label covers the label including its `:`, the range of an instruction
+/// covers its opcode and argument. Comments and surrounding whitespace are not included.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::parse_spanned;
+/// use ramemu::stmt::Stmt;
+///
+/// let stmts: Vec<_> = parse_spanned("end: HALT").collect();
+/// assert_eq!(
+///   stmts,
+///   vec![
+///     Ok((Stmt::Label("end".to_string(), 1), 0..4)),
+///     Ok((Stmt::Halt(1), 5..9)),
+///   ]
+/// );
+/// ```
+pub fn parse_spanned(
+  source: &str,
+) -> impl Iterator<Item = Result<(Stmt, Range<usize>), ParseError>> + '_ {
+  source.lines().enumerate().flat_map(move |(i, l)| {
+    let offset = span_of(source, l).start;
+    match parse_line(l.trim(), i + 1) {
+      Ok(stmts) => stmts
+        .into_iter()
+        .zip(statement_spans(l, DEFAULT_COMMENT_CHARS))
+        .map(|(stmt, span)| Ok((stmt, span.start + offset..span.end + offset)))
+        .collect(),
+      Err(error) => vec![Err(error)],
+    }
+  })
+}
+
+/// Parses the source code like [`parse_with_options`], keeping the comment of every line.
+///
+/// The comment is everything after the first of the [`ParserOptions::comment_chars`] of a
+/// line, trimmed, and is attached to the last statement of the line. Other statements, and
+/// lines without a comment, carry `None`. Lines holding only a comment yield nothing.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::{parse_with_comments, ParserOptions};
+/// use ramemu::stmt::Stmt;
+///
+/// let stmts: Vec<_> = parse_with_comments("HALT # done", ParserOptions::default()).collect();
+/// assert_eq!(stmts, vec![Ok((Stmt::Halt(1), Some("done".to_string())))]);
+/// ```
+pub fn parse_with_comments(
+  source: &str,
+  options: ParserOptions,
+) -> impl Iterator<Item = Result<(Stmt, Option<String>), ParseError>> + '_ {
+  source.lines().enumerate().flat_map(move |(i, l)| {
+    let comment = l
+      .split_once(options.comment_chars.as_slice())
+      .map(|(_, comment)| comment.trim().to_string());
+    match parse_line_with_options(l.trim(), i + 1, &options) {
+      Ok(stmts) => {
+        let last = stmts.len().saturating_sub(1);
+        stmts
+          .into_iter()
+          .enumerate()
+          .map(|(index, stmt)| Ok((stmt, comment.clone().filter(|_| index == last))))
+          .collect()
+      }
+      Err(error) => vec![Err(error)],
+    }
+  })
+}
+
+/// Parses the source code read line by line from `reader`, like [`parse`].
+///
+/// Unlike [`parse`], the source does not have to be loaded into memory as a whole. Line
+/// numbers are preserved. If reading a line fails, a [`ParseError::Io`] carrying that line is
+/// yielded and the iterator ends.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+///
+/// use ramemu::parser::parse_reader;
+/// use ramemu::stmt::{Stmt, Value};
+///
+/// let stmts: Vec<_> = parse_reader(Cursor::new("LOAD =1\nHALT")).collect();
+/// assert_eq!(stmts, vec![Ok(Stmt::Load(Value::Pure(1), 1)), Ok(Stmt::Halt(2))]);
+/// ```
+#[cfg(feature = "std")]
+pub fn parse_reader<R: BufRead>(reader: R) -> impl Iterator<Item = Result<Stmt, ParseError>> {
+  reader
+    .lines()
+    .enumerate()
+    .scan(false, |failed, (i, line)| {
+      if *failed {
+        return None;
+      }
+      let stmts = match line {
+        Ok(line) => match parse_line(line.trim(), i + 1) {
+          Ok(stmts) => stmts.into_iter().map(Ok).collect(),
+          Err(error) => vec![Err(error)],
+        },
+        Err(_) => {
+          *failed = true;
+          vec![Err(ParseError::Io(i + 1))]
+        }
+      };
+      Some(stmts)
+    })
+    .flatten()
+}
+
+/// Parses the whole source code, separating successfully parsed statements from errors.
+///
+/// Unlike [`parse`], which leaves short-circuiting to the caller, this function drives the
+/// whole iterator so every error in the source can be reported at once. Both vectors keep
+/// the order in which statements and errors appear in the source.
+pub fn parse_all(source: &str) -> (Vec<Stmt>, Vec<ParseError>) {
+  let mut stmts = Vec::new();
+  let mut errors = Vec::new();
+  for result in parse(source) {
+    match result {
+      Ok(stmt) => stmts.push(stmt),
+      Err(error) => errors.push(error),
+    }
+  }
+  (stmts, errors)
+}
+
+/// Parses the whole source code into a vector of statements, stopping at the first error.
+///
+/// The vector is pre-sized by the number of lines, so it usually doesn't reallocate. Use
+/// [`parse_all`] to get every error in the source instead.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::parse_collect;
+/// use ramemu::stmt::Stmt;
+///
+/// assert_eq!(parse_collect("NOP\nHALT"), Ok(vec![Stmt::Nop(1), Stmt::Halt(2)]));
+/// assert!(parse_collect("FOO\nHALT").is_err());
+/// ```
+pub fn parse_collect(source: &str) -> Result<Vec<Stmt>, ParseError> {
+  let mut stmts = Vec::with_capacity(source.lines().count());
+  for result in parse(source) {
+    stmts.push(result?);
+  }
+  Ok(stmts)
+}
+
+/// Limits on the size of the source code accepted by [`parse_with_limits`].
+///
+/// The default limits accept any source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+  /// The maximum number of lines.
+  pub max_lines: usize,
+  /// The maximum length of a line in bytes, not counting the line break.
+  pub max_line_len: usize,
+}
+
+impl Default for ParseLimits {
+  #[inline]
+  fn default() -> Self {
+    ParseLimits {
+      max_lines: usize::MAX,
+      max_line_len: usize::MAX,
+    }
+  }
+}
+
+/// Parses the whole source code like [`parse_collect`], after checking it against `limits`.
+///
+/// The limits are checked before anything is parsed, so oversized input, e.g. pasted into a
+/// web playground, is rejected cheaply with [`ParseError::TooManyLines`] or
+/// [`ParseError::LineTooLong`].
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::errors::ParseError;
+/// use ramemu::parser::{parse_with_limits, ParseLimits};
+///
+/// let limits = ParseLimits { max_lines: 2, max_line_len: 16 };
+/// assert!(parse_with_limits("NOP\nHALT", limits).is_ok());
+/// assert_eq!(
+///   parse_with_limits("NOP\nNOP\nHALT", limits),
+///   Err(ParseError::TooManyLines(3))
+/// );
+/// ```
+pub fn parse_with_limits(source: &str, limits: ParseLimits) -> Result<Vec<Stmt>, ParseError> {
+  for (i, line) in source.lines().enumerate() {
+    if i >= limits.max_lines {
+      Err(ParseError::TooManyLines(i + 1))?
+    }
+    if line.len() > limits.max_line_len {
+      Err(ParseError::LineTooLong(i + 1, line.len()))?
+    }
+  }
+  parse_collect(source)
+}
+
+/// Parses the whole source code like [`parse_collect`], expanding `REPEAT n` ... `ENDREPEAT`
+/// blocks into `n` copies of the statements between them.
+///
+/// `REPEAT` and `ENDREPEAT` must be on lines of their own and blocks may be nested. The
+/// copies keep the line numbers of the original statements. Labels would no longer be unique
+/// once copied, so defining a label or an alias inside a block fails with
+/// [`ParseError::UnsupportedSyntax`], as do an `ENDREPEAT` without a matching `REPEAT` and a
+/// `REPEAT` that is never closed.
+///
+/// Nested blocks multiply their counts, so a few lines can expand into an enormous program.
+/// Expanding into more than `max_statements` statements fails with
+/// [`ParseError::TooManyStatements`] before anything is copied.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::{parse_with_repeats, ParserOptions};
+/// use ramemu::stmt::{Stmt, Value};
+///
+/// let source = "REPEAT 2\nWRITE =1\nENDREPEAT\nHALT";
+/// let stmts = parse_with_repeats(source, &ParserOptions::default(), 100).unwrap();
+/// assert_eq!(
+///   stmts,
+///   vec![
+///     Stmt::Output(Value::Pure(1), 2),
+///     Stmt::Output(Value::Pure(1), 2),
+///     Stmt::Halt(4),
+///   ]
+/// );
+/// ```
+pub fn parse_with_repeats(
+  source: &str,
+  options: &ParserOptions,
+  max_statements: usize,
+) -> Result<Vec<Stmt>, ParseError> {
+  // Every open block with its line, the span of its `REPEAT`, its count and its statements,
+  // below the statements outside of any block.
+  let mut blocks: Vec<(usize, Span, usize, Vec<Stmt>)> = Vec::new();
+  let mut stmts = Vec::new();
+  // The number of statements in `stmts` and every open block.
+  let mut total = 0usize;
+
+  for (i, line) in source.lines().enumerate() {
+    let (line, number) = (line.trim(), i + 1);
+    let code = line
+      .split(options.comment_chars.as_slice())
+      .next()
+      .unwrap_or("");
+    let facts: Vec<_> = code.split_whitespace().collect();
+
+    match facts.first().map(|head| head.to_uppercase()).as_deref() {
+      Some("REPEAT") => {
+        let head = span_of(line, facts[0]);
+        let count = facts
+          .get(1)
+          .ok_or(ParseError::ArgumentIsRequired(number, head))?;
+        let span = span_of(line, count);
+        if let Some(extra) = facts.get(2) {
+          Err(ParseError::UnsupportedSyntax(number, span_of(line, extra)))?
+        }
+        let count = parse_integer(count)
+          .map_err(|argument| ParseError::invalid_argument(number, span, argument, count))?;
+        blocks.push((number, head, count, Vec::new()));
+      }
+      Some("ENDREPEAT") => {
+        let head = span_of(line, facts[0]);
+        if let Some(extra) = facts.get(1) {
+          Err(ParseError::UnsupportedSyntax(number, span_of(line, extra)))?
+        }
+        let (_, _, count, block) = blocks
+          .pop()
+          .ok_or(ParseError::UnsupportedSyntax(number, head))?;
+        total = block
+          .len()
+          .checked_mul(count)
+          .and_then(|copies| copies.checked_add(total - block.len()))
+          .filter(|total| *total <= max_statements)
+          .ok_or(ParseError::TooManyStatements(number))?;
+        let outer = blocks.last_mut().map_or(&mut stmts, |(.., outer)| outer);
+        outer.extend(block.iter().cycle().take(block.len() * count).cloned());
+      }
+      _ => {
+        let parsed = parse_line_with_options(line, number, options)?;
+        total += parsed.len();
+        if total > max_statements {
+          Err(ParseError::TooManyStatements(number))?
+        }
+        match blocks.last_mut() {
+          Some((.., block)) => {
+            if let Some(k) = parsed
+              .iter()
+              .position(|stmt| matches!(stmt, Stmt::Label(..) | Stmt::Alias(..)))
+            {
+              Err(ParseError::UnsupportedSyntax(
+                number,
+                statement_spans(line, &options.comment_chars)[k],
+              ))?
+            }
+            block.extend(parsed);
+          }
+          None => stmts.extend(parsed),
+        }
+      }
+    }
+  }
+
+  match blocks.pop() {
+    Some((line, head, ..)) => Err(ParseError::UnsupportedSyntax(line, head)),
+    None => Ok(stmts),
+  }
+}
+
+/// Parses the whole source code into a [`ParseOutcome`].
+///
+/// This is the same as [`parse_all`], with the statements and errors gathered in a named
+/// struct. Labels named like an opcode are accepted and reported as warnings.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::parse_program;
+///
+/// let outcome = parse_program("LOAD =1\nFOO 2\nHALT");
+/// assert_eq!(outcome.statements.len(), 2);
+/// assert_eq!(outcome.errors.len(), 1);
+/// assert!(!outcome.is_ok());
+///
+/// let outcome = parse_program("HALT: HALT");
+/// assert!(outcome.is_ok());
+/// assert_eq!(outcome.warnings.len(), 1);
+/// ```
+pub fn parse_program(source: &str) -> ParseOutcome {
+  let options = ParserOptions::default();
+  let mut outcome = ParseOutcome::default();
+  for (i, l) in source.lines().enumerate() {
+    match parse_line_with_warnings(l.trim(), i + 1, &options, &mut outcome.warnings) {
+      Ok(stmts) => outcome.statements.extend(stmts),
+      Err(error) => outcome.errors.push(error),
+    }
+  }
+  outcome
+}
+
+/// Parses the source code and re-emits it in a canonical format.
+///
+/// Opcodes are uppercased with a single space before the argument, labels are put on their
+/// own line, surrounding whitespace is removed and runs of blank lines are collapsed into one.
+/// Comments are preserved: trailing comments stay after the last statement of their line, and
+/// comment-only lines are kept as they are. Returns the first [`ParseError`] encountered.
+pub fn format_source(source: &str) -> Result<String, ParseError> {
+  let mut lines: Vec<String> = Vec::new();
+
+  for (i, line) in source.lines().enumerate() {
+    let line = line.trim();
+    let (code, comment) = match line.split_once('#') {
+      Some((code, comment)) => (code, Some(comment.trim())),
+      None => (line, None),
+    };
+
+    let mut formatted: Vec<String> = parse_line(code.trim(), i + 1)?
+      .iter()
+      .map(Stmt::to_string)
+      .collect();
+
+    if let Some(comment) = comment {
+      let comment = format!("# {comment}");
+      match formatted.last_mut() {
+        Some(last) => *last = format!("{last} {comment}"),
+        None => formatted.push(comment.trim_end().to_string()),
+      }
+    }
+
+    if formatted.is_empty() {
+      if lines.last().is_some_and(|last| !last.is_empty()) {
+        lines.push(String::new());
+      }
+    } else {
+      lines.extend(formatted);
+    }
+  }
+
+  while lines.last().is_some_and(String::is_empty) {
+    lines.pop();
+  }
+
+  Ok(lines.into_iter().map(|line| line + "\n").collect())
+}
+
+/// Checks that every jump in `stmts` targets a label defined somewhere in `stmts`.
+///
+/// Forward and backward references are both allowed. Every jump to an undefined label, and
+/// every label address (`=@name`) of an undefined label, is reported as a
+/// [`ParseError::UnknownLabel`] carrying the line of the statement and the missing label
+/// name, in the order the statements appear. A jump may also target an alias defined with
+/// `ALIAS`; every alias must eventually refer to a label, otherwise it is reported as a
+/// [`ParseError::UnknownLabel`] carrying the missing target or as a
+/// [`ParseError::AliasCycle`].
+pub fn validate_labels(stmts: &[Stmt]) -> Result<(), Vec<ParseError>> {
+  let defined: BTreeSet<&str> = stmts
+    .iter()
+    .filter_map(|stmt| match stmt {
+      Stmt::Label(label, _) => Some(label.as_str()),
+      _ => None,
+    })
+    .collect();
+  let aliases: BTreeMap<&str, &str> = stmts
+    .iter()
+    .filter_map(|stmt| match stmt {
+      Stmt::Alias(name, target, _) => Some((name.as_str(), target.get())),
+      _ => None,
+    })
+    .collect();
+
+  let errors: Vec<ParseError> = stmts
+    .iter()
+    .filter_map(|stmt| match stmt {
+      Stmt::Jump(label, line)
+      | Stmt::JumpIfZero(label, line)
+      | Stmt::JumpGreaterZero(label, line)
+      | Stmt::JumpLessZero(label, line)
+      | Stmt::JumpNotZero(label, line)
+      | Stmt::Load(Value::LabelAddr(label), line)
+      | Stmt::Add(Value::LabelAddr(label), line)
+      | Stmt::Sub(Value::LabelAddr(label), line)
+      | Stmt::Mul(Value::LabelAddr(label), line)
+      | Stmt::Div(Value::LabelAddr(label), line)
+      | Stmt::Mod(Value::LabelAddr(label), line)
+      | Stmt::Pow(Value::LabelAddr(label), line)
+      | Stmt::Output(Value::LabelAddr(label), line)
+        if !defined.contains(label.get()) && !aliases.contains_key(label.get()) =>
+      {
+        Some(ParseError::UnknownLabel(*line, label.get().to_string()))
+      }
+      Stmt::Alias(name, target, line) => {
+        let mut visited = BTreeSet::from([name.as_str()]);
+        let mut target = target.get();
+        while !defined.contains(target) {
+          let Some(next) = aliases.get(target) else {
+            return Some(ParseError::UnknownLabel(*line, target.to_string()));
+          };
+          if !visited.insert(target) {
+            return Some(ParseError::AliasCycle(*line, name.clone()));
+          }
+          target = next;
+        }
+        None
+      }
+      _ => None,
+    })
     .collect();
 
-  if facts.len() > 2 {
-    Err(ParseError::UnsupportedSyntax(line))?
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
   }
+}
 
-  if facts.is_empty() {
-    return Ok(None);
+/// Parses a single line of source code and returns a [`Result`] containing a [`Vec<Stmt>`]
+/// or a [`ParseError`].
+///
+/// This function processes a single line of source code, returning an empty vector for empty
+/// lines or lines containing only comments. A line may hold a label, an instruction, or a label
+/// followed by an instruction (`loop: LOAD =1`), in which case both statements share the same
+/// line number. Several statements can be separated by `;` (`LOAD =1; ADD =2; HALT`); each
+/// segment is parsed independently, empty segments are ignored. Labels can also be defined
+/// with the `LABEL name` directive, and aliased with `ALIAS name target`. In case of a parsing error, it returns a [`ParseError`] whose [`Span`] points at
+/// the offending token inside `source`.
+pub fn parse_line(source: &str, line: usize) -> Result<Vec<Stmt>, ParseError> {
+  parse_line_with_options(source, line, &ParserOptions::default())
+}
+
+/// Parses a single statement, e.g. typed into a REPL, with line number `0`.
+///
+/// Returns `None` for an empty line or a line holding only a comment. A line holding more
+/// than one statement, such as `loop: HALT` or `NOP; HALT`, fails with
+/// [`ParseError::UnsupportedSyntax`] pointing at the extra statements.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::parser::parse_one;
+/// use ramemu::stmt::{Stmt, Value};
+///
+/// assert_eq!(parse_one("LOAD =1"), Ok(Some(Stmt::Load(Value::Pure(1), 0))));
+/// assert_eq!(parse_one("# nothing"), Ok(None));
+/// ```
+pub fn parse_one(source: &str) -> Result<Option<Stmt>, ParseError> {
+  let mut stmts = parse_line(source, 0)?;
+  if stmts.len() > 1 {
+    let spans = statement_spans(source, DEFAULT_COMMENT_CHARS);
+    let span = Span::new(spans[1].start, spans[spans.len() - 1].end);
+    Err(ParseError::UnsupportedSyntax(0, span))?
+  }
+  Ok(stmts.pop())
+}
+
+/// Parses a single line of source code like [`parse_line_with_options`], also returning how
+/// the line was split into tokens.
+///
+/// The [`LineLex`] is computed whether or not parsing succeeds, so e.g. an editor can still
+/// highlight the opcode and the comment of an erroneous line.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::errors::Span;
+/// use ramemu::parser::{try_parse_line, ParserOptions};
+///
+/// let (result, lex) = try_parse_line("LOAD x # oops", 1, &ParserOptions::default());
+/// assert!(result.is_err());
+/// assert_eq!(lex.opcode, Some(Span::new(0, 4)));
+/// assert_eq!(lex.argument, Some(Span::new(5, 6)));
+/// assert_eq!(lex.comment, Some(Span::new(7, 13)));
+/// ```
+pub fn try_parse_line(
+  source: &str,
+  line: usize,
+  options: &ParserOptions,
+) -> (Result<Vec<Stmt>, ParseError>, LineLex) {
+  (
+    parse_line_with_options(source, line, options),
+    lex_line(source, &options.comment_chars),
+  )
+}
+
+/// Parses a single line of source code like [`parse_line`], using the given [`ParserOptions`].
+pub fn parse_line_with_options(
+  source: &str,
+  line: usize,
+  options: &ParserOptions,
+) -> Result<Vec<Stmt>, ParseError> {
+  parse_line_with_warnings(source, line, options, &mut Vec::new())
+}
+
+/// Parses a single line of source code like [`parse_line_with_options`], adding problems
+/// that don't prevent parsing to `warnings`.
+fn parse_line_with_warnings(
+  source: &str,
+  line: usize,
+  options: &ParserOptions,
+  warnings: &mut Vec<ParseError>,
+) -> Result<Vec<Stmt>, ParseError> {
+  let code = source
+    .split(options.comment_chars.as_slice())
+    .next()
+    .unwrap_or("");
+  let mut stmts = Vec::new();
+  for segment in code.split(';') {
+    parse_segment(source, segment, line, options, &mut stmts, warnings)?;
   }
+  Ok(stmts)
+}
 
-  let head = facts[0].trim();
-  let tail = facts.get(1);
+/// The opcodes understood by the parser, in upper case.
+///
+/// Checked before the arguments are counted, so an unknown opcode is reported as such
+/// whatever its arguments. Every entry must be handled by [`parse_segment`].
+const OPCODES: &[&str] = &[
+  "LOAD", "ADD", "SUB", "MUL", "DIV", "MOD", "POW", "WRITE", "OUTPUT", "JUMP", "JMP", "JZ",
+  "JZERO", "JGZ", "JGTZ", "JLZ", "JLTZ", "JNZ", "JNEZERO", "STORE", "INPUT", "READ", "JIND", "INC",
+  "DEC", "SWAP", "LABEL", "ALIAS", "NOP", "HALT",
+];
+
+/// Parses a single `;` separated segment of `source` into `stmts`, adding warnings to
+/// `warnings`.
+///
+/// Spans are computed relative to `source`, which `segment` is a slice of.
+fn parse_segment(
+  source: &str,
+  segment: &str,
+  line: usize,
+  options: &ParserOptions,
+  stmts: &mut Vec<Stmt>,
+  warnings: &mut Vec<ParseError>,
+) -> Result<(), ParseError> {
+  let facts: Vec<_> = segment.split_whitespace().collect();
+  let mut facts = facts.as_slice();
 
-  if let Some(label) = head.strip_suffix(':') {
-    if is_valid_label(label) {
-      return Ok(Some(Stmt::Label(label.to_string(), line)));
+  if let Some(head) = facts.first() {
+    if let Some(label) = head.strip_suffix(':') {
+      if label.is_empty() {
+        Err(ParseError::EmptyLabel(line, span_of(source, head)))?
+      }
+      if !is_valid_label(label, options) {
+        Err(ParseError::LabelIsNotValid(line, span_of(source, head)))?
+      }
+      options.check_reserved(label, line, span_of(source, head), warnings)?;
+      stmts.push(Stmt::Label(options.label(label), line));
+      facts = &facts[1..];
     }
-    Err(ParseError::LabelIsNotValid(line))?
   }
 
-  let opcode = head.to_uppercase();
+  let Some(head) = facts.first() else {
+    return Ok(());
+  };
 
-  let stmt = match opcode.as_str() {
-    "LOAD" | "ADD" | "SUB" | "MUL" | "DIV" | "WRITE" | "OUTPUT" => parse_with_value(
-      &opcode,
-      tail.ok_or(ParseError::ArgumentIsRequired(line))?,
-      line,
-    )?,
-    "JUMP" | "JMP" | "JZ" | "JZERO" | "JGZ" | "JGTZ" => parse_with_label(
-      &opcode,
-      tail.ok_or(ParseError::ArgumentIsRequired(line))?,
+  let head_span = span_of(source, head);
+  let opcode = options.opcode(head);
+  if !OPCODES.contains(&opcode.as_str()) {
+    Err(ParseError::UnsupportedOpcode(
       line,
-    )?,
-    "STORE" | "INPUT" | "READ" => parse_with_register(
-      &opcode,
-      tail.ok_or(ParseError::ArgumentIsRequired(line))?,
+      head_span,
+      head.to_string(),
+    ))?
+  }
+
+  // `ALIAS` is the only opcode taking two arguments.
+  let arguments = if opcode == "ALIAS" { 2 } else { 1 };
+  if facts.len() > arguments + 1 {
+    let start = span_of(source, facts[arguments + 1]).start;
+    let end = span_of(source, facts[facts.len() - 1]).end;
+    Err(ParseError::UnsupportedSyntax(line, Span::new(start, end)))?
+  }
+
+  let tail = facts
+    .get(1)
+    .map(|tail| (*tail, span_of(source, tail)))
+    .ok_or(ParseError::ArgumentIsRequired(line, head_span));
+
+  let stmt = match opcode.as_str() {
+    "LOAD" | "ADD" | "SUB" | "MUL" | "DIV" | "MOD" | "POW" => {
+      let (tail, span) = tail?;
+      parse_with_value(&opcode, tail, line, span, options)?
+    }
+    "WRITE" | "OUTPUT" => match tail {
+      Ok((tail, span)) => parse_with_value(&opcode, tail, line, span, options)?,
+      // Without an argument the accumulator is written, like `WRITE 0`.
+      Err(_) => Stmt::Output(Value::Register(RegisterValue::Direct(0)), line),
+    },
+    "JUMP" | "JMP" | "JZ" | "JZERO" | "JGZ" | "JGTZ" | "JLZ" | "JLTZ" | "JNZ" | "JNEZERO" => {
+      let (tail, span) = tail?;
+      parse_with_label(&opcode, tail, line, span, options)?
+    }
+    "STORE" | "INPUT" | "READ" | "JIND" | "INC" | "DEC" | "SWAP" => {
+      let (tail, span) = tail?;
+      parse_with_register(&opcode, tail, line, span)?
+    }
+    "LABEL" => {
+      let (tail, span) = tail?;
+      if !is_valid_label(tail, options) {
+        Err(ParseError::LabelIsNotValid(line, span))?
+      }
+      options.check_reserved(tail, line, span, warnings)?;
+      Stmt::Label(options.label(tail), line)
+    }
+    "ALIAS" => {
+      let (name, name_span) = tail?;
+      let target = facts
+        .get(2)
+        .ok_or(ParseError::ArgumentIsRequired(line, head_span))?;
+      let target_span = span_of(source, target);
+      if !is_valid_label(name, options) {
+        Err(ParseError::LabelIsNotValid(line, name_span))?
+      }
+      if !is_valid_label(target, options) {
+        Err(ParseError::LabelIsNotValid(line, target_span))?
+      }
+      options.check_reserved(name, line, name_span, warnings)?;
+      Stmt::Alias(options.label(name), Label::new(options.label(target)), line)
+    }
+    "NOP" => match tail {
+      Ok((_, span)) => Err(ParseError::UnsupportedSyntax(line, span))?,
+      Err(_) => Stmt::Nop(line),
+    },
+    "HALT" => match tail {
+      Ok((tail, span)) => {
+        let invalid = |argument| ParseError::invalid_argument(line, span, argument, tail);
+        Stmt::HaltCode(parse_integer(tail).map_err(invalid)?, line)
+      }
+      Err(_) => Stmt::Halt(line),
+    },
+    _ => Err(ParseError::UnsupportedOpcode(
       line,
-    )?,
-    "HALT" => Stmt::Halt(line),
-    _ => Err(ParseError::UnsupportedOpcode(line, opcode))?,
+      head_span,
+      head.to_string(),
+    ))?,
   };
 
-  Ok(Some(stmt))
+  stmts.push(stmt);
+  Ok(())
 }
 
-fn parse_with_register(opcode: &str, tail: &str, line: usize) -> Result<Stmt, ParseError> {
+fn parse_with_register(
+  opcode: &str,
+  tail: &str,
+  line: usize,
+  span: Span,
+) -> Result<Stmt, ParseError> {
+  let token = tail;
+  let invalid = |argument| ParseError::ArgumentIsNotValid(line, span, argument, token.to_string());
   let arg: RegisterValue = {
     if let Some(tail) = tail.strip_prefix('*') {
-      RegisterValue::Indirect(
-        tail
-          .parse()
-          .map_err(|_| ParseError::argument_value_must_be_numeric(line))?,
-      )
-    } else if let Ok(arg) = tail.parse::<usize>() {
-      RegisterValue::Direct(arg)
-    } else if tail.starts_with('=') {
-      Err(ParseError::pure_argument_not_allowed(line))?
+      RegisterValue::Indirect(parse_integer(tail).map_err(invalid)?)
+    } else if let Some(tail) = tail.strip_prefix('@') {
+      RegisterValue::Relative(parse_integer(tail).map_err(invalid)?)
     } else {
-      Err(ParseError::not_valid_argument(line))?
+      match parse_integer(tail) {
+        Ok(arg) => RegisterValue::Direct(arg),
+        Err(InvalidArgument::ArgumentValueOutOfRange) => {
+          Err(invalid(InvalidArgument::ArgumentValueOutOfRange))?
+        }
+        Err(_) if tail.starts_with('=') => {
+          Err(ParseError::pure_argument_not_allowed(line, span, token))?
+        }
+        Err(_) => Err(ParseError::not_valid_argument(line, span, token))?,
+      }
     }
   };
   match opcode {
     "STORE" => Ok(Stmt::Store(arg, line)),
     "INPUT" | "READ" => Ok(Stmt::Input(arg, line)),
-    _ => unreachable!("Opcodes were chenged in parse function, but not there"),
+    "JIND" => Ok(Stmt::JumpIndirect(arg, line)),
+    "INC" => Ok(Stmt::Inc(arg, line)),
+    "DEC" => Ok(Stmt::Dec(arg, line)),
+    "SWAP" => Ok(Stmt::Swap(arg, line)),
+    _ => unreachable!("parse_segment only passes the opcodes handled here"),
   }
 }
 
-fn parse_with_value(head: &str, tail: &str, line: usize) -> Result<Stmt, ParseError> {
+fn parse_with_value(
+  head: &str,
+  tail: &str,
+  line: usize,
+  span: Span,
+  options: &ParserOptions,
+) -> Result<Stmt, ParseError> {
+  let token = tail;
+  let invalid = |argument| ParseError::ArgumentIsNotValid(line, span, argument, token.to_string());
   let arg: Value = {
-    if let Some(tail) = tail.strip_prefix('=') {
-      Value::Pure(
-        tail
-          .parse()
-          .map_err(|_| ParseError::argument_value_must_be_numeric(line))?,
-      )
+    if let Some(label) = tail.strip_prefix("=@") {
+      if !is_valid_label(label, options) {
+        Err(invalid(InvalidArgument::LabelIsNotValid))?
+      }
+      Value::LabelAddr(Label::new(options.label(label)))
+    } else if let Some(tail) = tail.strip_prefix('=') {
+      Value::Pure(parse_integer(tail).map_err(invalid)?)
     } else if let Some(tail) = tail.strip_prefix('*') {
       Value::Register(RegisterValue::Indirect(
-        tail
-          .parse()
-          .map_err(|_| ParseError::argument_value_must_be_numeric(line))?,
+        parse_integer(tail).map_err(invalid)?,
+      ))
+    } else if let Some(tail) = tail.strip_prefix('@') {
+      Value::Register(RegisterValue::Relative(
+        parse_integer(tail).map_err(invalid)?,
       ))
-    } else if let Ok(arg) = tail.parse::<usize>() {
-      Value::Register(RegisterValue::Direct(arg))
     } else {
-      Err(ParseError::not_valid_argument(line))?
+      match parse_integer(tail) {
+        Ok(arg) => Value::Register(RegisterValue::Direct(arg)),
+        Err(InvalidArgument::ArgumentValueOutOfRange) => {
+          Err(invalid(InvalidArgument::ArgumentValueOutOfRange))?
+        }
+        Err(_) => Err(ParseError::not_valid_argument(line, span, token))?,
+      }
     }
   };
 
@@ -131,37 +1027,1353 @@ fn parse_with_value(head: &str, tail: &str, line: usize) -> Result<Stmt, ParseEr
     "SUB" => Ok(Stmt::Sub(arg, line)),
     "MUL" => Ok(Stmt::Mul(arg, line)),
     "DIV" => Ok(Stmt::Div(arg, line)),
-    _ => unreachable!("Opcodes were chenged in parse function, but not there"),
+    "MOD" => Ok(Stmt::Mod(arg, line)),
+    "POW" => Ok(Stmt::Pow(arg, line)),
+    _ => unreachable!("parse_segment only passes the opcodes handled here"),
   }
 }
 
-fn parse_with_label(head: &str, tail: &str, line: usize) -> Result<Stmt, ParseError> {
-  let label: Label = if is_valid_label(tail) {
-    Label::new(tail.to_string())
+fn parse_with_label(
+  head: &str,
+  tail: &str,
+  line: usize,
+  span: Span,
+  options: &ParserOptions,
+) -> Result<Stmt, ParseError> {
+  let label: Label = if is_valid_label(tail, options) {
+    Label::new(options.label(tail))
   } else {
-    Err(ParseError::LabelIsNotValid(line))?
+    Err(ParseError::LabelIsNotValid(line, span))?
   };
 
   match head {
     "JUMP" | "JMP" => Ok(Stmt::Jump(label, line)),
     "JZ" | "JZERO" => Ok(Stmt::JumpIfZero(label, line)),
-    "JGZ" | "JGTZ" => Ok(Stmt::JumpGreatherZero(label, line)),
-    _ => unreachable!("Opcodes were chenged in parse function, but not there"),
+    "JGZ" | "JGTZ" => Ok(Stmt::JumpGreaterZero(label, line)),
+    "JNZ" | "JNEZERO" => Ok(Stmt::JumpNotZero(label, line)),
+    "JLZ" | "JLTZ" => Ok(Stmt::JumpLessZero(label, line)),
+    _ => unreachable!("parse_segment only passes the opcodes handled here"),
   }
 }
 
+/// Parses an integer literal into the requested integer type.
+///
+/// The literal may start with a `-` or `+` sign followed by a `0x`/`0X` (hexadecimal),
+/// `0b`/`0B` (binary) or `0o`/`0O` (octal) prefix. Without a prefix it is parsed as decimal.
+/// Single `_` separators are allowed between digits, e.g. `1_000_000`.
+/// Fails with [`InvalidArgument::ArgumentValueMustBeNumeric`] if the literal is malformed
+/// and with [`InvalidArgument::ArgumentValueOutOfRange`] if it does not fit into `T`.
+fn parse_integer<T: TryFrom<i128>>(literal: &str) -> Result<T, InvalidArgument> {
+  let (negative, unsigned) = match literal.strip_prefix('-') {
+    Some(unsigned) => (true, unsigned),
+    None => (false, literal.strip_prefix('+').unwrap_or(literal)),
+  };
+
+  let (radix, digits) = match unsigned.get(..2) {
+    Some("0x" | "0X") => (16, &unsigned[2..]),
+    Some("0b" | "0B") => (2, &unsigned[2..]),
+    Some("0o" | "0O") => (8, &unsigned[2..]),
+    _ => (10, unsigned),
+  };
+
+  if digits.starts_with(['-', '+', '_']) || digits.ends_with('_') || digits.contains("__") {
+    return Err(InvalidArgument::ArgumentValueMustBeNumeric);
+  }
+
+  let value =
+    i128::from_str_radix(&digits.replace('_', ""), radix).map_err(|error| match error.kind() {
+      IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => {
+        InvalidArgument::ArgumentValueOutOfRange
+      }
+      _ => InvalidArgument::ArgumentValueMustBeNumeric,
+    })?;
+  let value = if negative { -value } else { value };
+  value
+    .try_into()
+    .map_err(|_| InvalidArgument::ArgumentValueOutOfRange)
+}
+
+/// Splits the first statement of a line into tokens, see [`LineLex`]. A comment starts at the
+/// first of the `comment_chars`.
+fn lex_line(source: &str, comment_chars: &[char]) -> LineLex {
+  let code = source.split(comment_chars).next().unwrap_or("");
+  let facts: Vec<_> = code
+    .split(';')
+    .next()
+    .unwrap_or("")
+    .split_whitespace()
+    .collect();
+  let mut lex = LineLex {
+    comment: (code.len() < source.len()).then(|| Span::new(code.len(), source.len())),
+    tokens: facts.len(),
+    ..LineLex::default()
+  };
+
+  let mut facts = facts.as_slice();
+  if let Some(head) = facts.first().filter(|head| head.ends_with(':')) {
+    lex.label = Some(span_of(source, head));
+    facts = &facts[1..];
+  }
+  if let [opcode, arguments @ ..] = facts {
+    lex.opcode = Some(span_of(source, opcode));
+    if let (Some(first), Some(last)) = (arguments.first(), arguments.last()) {
+      lex.argument = Some(Span::new(
+        span_of(source, first).start,
+        span_of(source, last).end,
+      ));
+    }
+  }
+  lex
+}
+
+/// Returns the spans of the statements on a line: for every `;` separated segment, the
+/// label, if any, followed by the instruction, if any. A comment starts at the first of the
+/// `comment_chars`.
+fn statement_spans(source: &str, comment_chars: &[char]) -> Vec<Span> {
+  let mut spans = Vec::new();
+  for segment in source.split(comment_chars).next().unwrap_or("").split(';') {
+    let facts: Vec<_> = segment.split_whitespace().collect();
+    let mut facts = facts.as_slice();
+
+    if let Some(head) = facts.first().filter(|head| head.ends_with(':')) {
+      spans.push(span_of(source, head));
+      facts = &facts[1..];
+    }
+    if let (Some(first), Some(last)) = (facts.first(), facts.last()) {
+      spans.push(Span::new(
+        span_of(source, first).start,
+        span_of(source, last).end,
+      ));
+    }
+  }
+  spans
+}
+
+/// Returns the byte span of `token` inside `source`.
+///
+/// `token` must be a subslice of `source`, which holds for every token produced by
+/// splitting `source` itself.
+#[inline]
+fn span_of(source: &str, token: &str) -> Span {
+  let start = token.as_ptr() as usize - source.as_ptr() as usize;
+  Span::new(start, start + token.len())
+}
+
 /// Checks if the given string is a valid label.
 ///
 /// A valid label must start with an ASCII alphabetic character or an underscore,
-/// and can contain ASCII alphanumeric characters, underscores, or digits.
-fn is_valid_label(label: &str) -> bool {
-  let Some(first) = label.chars().next() else { return false };
-
-  if !first.is_ascii_alphabetic() && first != '_' {
+/// and can contain ASCII alphanumeric characters, underscores, or digits. With
+/// [`ParserOptions::unicode_labels`] enabled, Unicode letters and digits are accepted too.
+fn is_valid_label(label: &str, options: &ParserOptions) -> bool {
+  let Some(first) = label.chars().next() else {
     return false;
+  };
+
+  if options.unicode_labels {
+    (first.is_alphabetic() || first == '_')
+      && label.chars().all(|c| c.is_alphanumeric() || c == '_')
+  } else {
+    (first.is_ascii_alphabetic() || first == '_')
+      && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_label_on_its_own_line() {
+    let result = parse_line("loop:", 3);
+
+    assert_eq!(result, Ok(vec![Stmt::Label("loop".to_string(), 3)]));
+  }
+
+  #[test]
+  fn test_label_and_instruction_on_same_line() {
+    let result = parse_line("loop: LOAD =1", 3);
+
+    assert_eq!(
+      result,
+      Ok(vec![
+        Stmt::Label("loop".to_string(), 3),
+        Stmt::Load(Value::Pure(1), 3),
+      ])
+    );
+  }
+
+  #[test]
+  fn test_label_and_invalid_instruction_on_same_line() {
+    let result = parse_line("loop: LOAD", 3);
+
+    assert_eq!(
+      result,
+      Err(ParseError::ArgumentIsRequired(3, Span::new(6, 10)))
+    );
   }
 
-  label
-    .chars()
-    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c.is_ascii_digit())
+  #[test]
+  fn test_parse_mod() {
+    assert_eq!(
+      parse_line("MOD =3", 0),
+      Ok(vec![Stmt::Mod(Value::Pure(3), 0)])
+    );
+    assert_eq!(
+      parse_line("mod *2", 0),
+      Ok(vec![Stmt::Mod(
+        Value::Register(RegisterValue::Indirect(2)),
+        0
+      )])
+    );
+  }
+
+  #[test]
+  fn test_parse_pow() {
+    assert_eq!(
+      parse_line("POW 4", 0),
+      Ok(vec![Stmt::Pow(
+        Value::Register(RegisterValue::Direct(4)),
+        0
+      )])
+    );
+    assert_eq!(
+      parse_line("POW =a", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(4, 6),
+        "=a"
+      ))
+    );
+    assert_eq!(
+      parse_line("POW", 0),
+      Err(ParseError::ArgumentIsRequired(0, Span::new(0, 3)))
+    );
+  }
+
+  #[test]
+  fn test_parse_signed_pure_values() {
+    assert_eq!(
+      parse_line("LOAD =-5", 0),
+      Ok(vec![Stmt::Load(Value::Pure(-5), 0)])
+    );
+    assert_eq!(
+      parse_line("ADD =-10", 0),
+      Ok(vec![Stmt::Add(Value::Pure(-10), 0)])
+    );
+    assert_eq!(
+      parse_line("SUB =+7", 0),
+      Ok(vec![Stmt::Sub(Value::Pure(7), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD =-", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(5, 7),
+        "=-"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_parse_radix_literals() {
+    assert_eq!(
+      parse_line("LOAD =0xFF", 0),
+      Ok(vec![Stmt::Load(Value::Pure(255), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD =0B1010", 0),
+      Ok(vec![Stmt::Load(Value::Pure(10), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD =0o17", 0),
+      Ok(vec![Stmt::Load(Value::Pure(15), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD =-0x10", 0),
+      Ok(vec![Stmt::Load(Value::Pure(-16), 0)])
+    );
+    assert_eq!(
+      parse_line("ADD *0x2", 0),
+      Ok(vec![Stmt::Add(
+        Value::Register(RegisterValue::Indirect(2)),
+        0
+      )])
+    );
+    assert_eq!(
+      parse_line("STORE 0b11", 0),
+      Ok(vec![Stmt::Store(RegisterValue::Direct(3), 0)])
+    );
+    assert_eq!(
+      parse_line("READ *0o10", 0),
+      Ok(vec![Stmt::Input(RegisterValue::Indirect(8), 0)])
+    );
+  }
+
+  #[test]
+  fn test_parse_invalid_radix_literals() {
+    assert_eq!(
+      parse_line("LOAD =0xFG", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(5, 10),
+        "=0xFG"
+      ))
+    );
+    assert_eq!(
+      parse_line("LOAD =0b102", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(5, 11),
+        "=0b102"
+      ))
+    );
+    assert_eq!(
+      parse_line("LOAD =0x", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(5, 8),
+        "=0x"
+      ))
+    );
+    assert_eq!(
+      parse_line("LOAD =0x-1", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(5, 10),
+        "=0x-1"
+      ))
+    );
+    assert_eq!(
+      parse_line("STORE *0o8", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(6, 10),
+        "*0o8"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_parse_digit_separators() {
+    assert_eq!(
+      parse_line("LOAD =1_000_000", 0),
+      Ok(vec![Stmt::Load(Value::Pure(1_000_000), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD =1_0", 0),
+      Ok(vec![Stmt::Load(Value::Pure(10), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD =0xFF_FF", 0),
+      Ok(vec![Stmt::Load(Value::Pure(0xFFFF), 0)])
+    );
+    assert_eq!(
+      parse_line("STORE 1_0", 0),
+      Ok(vec![Stmt::Store(RegisterValue::Direct(10), 0)])
+    );
+    for literal in ["=_1", "=1_", "=1__0", "=0x_1"] {
+      assert_eq!(
+        parse_line(&format!("LOAD {literal}"), 0),
+        Err(ParseError::argument_value_must_be_numeric(
+          0,
+          Span::new(5, 5 + literal.len()),
+          literal
+        ))
+      );
+    }
+  }
+
+  #[test]
+  fn test_parse_jump_indirect() {
+    assert_eq!(
+      parse_line("JIND 2", 0),
+      Ok(vec![Stmt::JumpIndirect(RegisterValue::Direct(2), 0)])
+    );
+    assert_eq!(
+      parse_line("jind *2", 0),
+      Ok(vec![Stmt::JumpIndirect(RegisterValue::Indirect(2), 0)])
+    );
+    assert_eq!(
+      parse_line("JIND =2", 0),
+      Err(ParseError::pure_argument_not_allowed(
+        0,
+        Span::new(5, 7),
+        "=2"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_parse_relative() {
+    assert_eq!(
+      parse_line("LOAD @3", 0),
+      Ok(vec![Stmt::Load(
+        Value::Register(RegisterValue::Relative(3)),
+        0
+      )])
+    );
+    assert_eq!(
+      parse_line("STORE @0x10", 0),
+      Ok(vec![Stmt::Store(RegisterValue::Relative(16), 0)])
+    );
+    assert_eq!(
+      parse_line("LOAD @-1", 0),
+      Err(ParseError::invalid_argument(
+        0,
+        Span::new(5, 8),
+        InvalidArgument::ArgumentValueOutOfRange,
+        "@-1"
+      ))
+    );
+    assert_eq!(
+      parse_line("STORE @x", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(6, 8),
+        "@x"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_parse_flattens_label_and_instruction() {
+    let stmts: Result<Vec<Stmt>, ParseError> = parse("start: JUMP start\nHALT").collect();
+
+    assert_eq!(
+      stmts,
+      Ok(vec![
+        Stmt::Label("start".to_string(), 1),
+        Stmt::Jump(Label::new("start".to_string()), 1),
+        Stmt::Halt(2),
+      ])
+    );
+  }
+
+  #[test]
+  fn test_semicolon_separated_statements() {
+    assert_eq!(
+      parse_line("LOAD =1; ADD =2;; HALT;", 3),
+      Ok(vec![
+        Stmt::Load(Value::Pure(1), 3),
+        Stmt::Add(Value::Pure(2), 3),
+        Stmt::Halt(3),
+      ])
+    );
+    assert_eq!(
+      parse_line("LOAD =1 # first; HALT", 1),
+      Ok(vec![Stmt::Load(Value::Pure(1), 1)])
+    );
+    assert_eq!(
+      parse_line("HALT; FOO 1", 1),
+      Err(ParseError::UnsupportedOpcode(
+        1,
+        Span::new(6, 9),
+        "FOO".to_string()
+      ))
+    );
+  }
+
+  #[test]
+  fn test_comment_chars() {
+    let options = ParserOptions {
+      comment_chars: vec!['#', ';'],
+      ..ParserOptions::default()
+    };
+    assert_eq!(
+      parse_line_with_options("LOAD =1 ; HALT", 1, &options),
+      Ok(vec![Stmt::Load(Value::Pure(1), 1)])
+    );
+    assert_eq!(
+      parse_line_with_options("; comment", 2, &options),
+      Ok(vec![])
+    );
+    assert_eq!(
+      parse_line_with_options("HALT # comment", 3, &options),
+      Ok(vec![Stmt::Halt(3)])
+    );
+  }
+
+  #[test]
+  fn test_parse_spanned() {
+    let source = "  LOAD =1 # one\nloop: JUMP loop\n";
+    assert_eq!(
+      parse_spanned(source).collect::<Vec<_>>(),
+      vec![
+        Ok((Stmt::Load(Value::Pure(1), 1), 2..9)),
+        Ok((Stmt::Label("loop".to_string(), 2), 16..21)),
+        Ok((Stmt::Jump(Label::new("loop".to_string()), 2), 22..31)),
+      ]
+    );
+    assert_eq!(&source[22..31], "JUMP loop");
+  }
+
+  #[test]
+  #[cfg(feature = "std")]
+  fn test_parse_reader() {
+    let source = "LOAD =1\n\n# comment\nloop: ADD =1\nJUMP loop\n";
+    let from_reader: Vec<_> = parse_reader(std::io::Cursor::new(source)).collect();
+    assert_eq!(from_reader, parse(source).collect::<Vec<_>>());
+    assert_eq!(from_reader[2], Ok(Stmt::Add(Value::Pure(1), 4)));
+
+    let invalid = std::io::Cursor::new(b"HALT\n\xff\nHALT\n".to_vec());
+    assert_eq!(
+      parse_reader(invalid).collect::<Vec<_>>(),
+      vec![Ok(Stmt::Halt(1)), Err(ParseError::Io(2))]
+    );
+  }
+
+  #[test]
+  fn test_label_directive() {
+    assert_eq!(
+      parse_line("LABEL loop", 1),
+      Ok(vec![Stmt::Label("loop".to_string(), 1)])
+    );
+    assert_eq!(
+      parse_line("label 1loop", 2),
+      Err(ParseError::LabelIsNotValid(2, Span::new(6, 11)))
+    );
+    assert_eq!(
+      parse_line("LABEL", 3),
+      Err(ParseError::ArgumentIsRequired(3, Span::new(0, 5)))
+    );
+  }
+
+  #[test]
+  fn test_jump_less_zero() {
+    let label = || Label::new("neg".to_string());
+    assert_eq!(
+      parse_line("JLZ neg", 1),
+      Ok(vec![Stmt::JumpLessZero(label(), 1)])
+    );
+    assert_eq!(
+      parse_line("jltz neg", 2),
+      Ok(vec![Stmt::JumpLessZero(label(), 2)])
+    );
+    assert_eq!(Stmt::JumpLessZero(label(), 1).to_string(), "JLTZ neg");
+  }
+
+  #[test]
+  fn test_jump_not_zero() {
+    let label = || Label::new("loop".to_string());
+    assert_eq!(
+      parse_line("JNZ loop", 1),
+      Ok(vec![Stmt::JumpNotZero(label(), 1)])
+    );
+    assert_eq!(
+      parse_line("jnezero loop", 2),
+      Ok(vec![Stmt::JumpNotZero(label(), 2)])
+    );
+    assert_eq!(Stmt::JumpNotZero(label(), 1).to_string(), "JNZ loop");
+    assert_eq!(
+      validate_labels(&parse_line("JNZ nowhere", 5).unwrap()),
+      Err(vec![ParseError::UnknownLabel(5, "nowhere".to_string())])
+    );
+  }
+
+  #[test]
+  fn test_nop() {
+    assert_eq!(parse_line("nop", 4), Ok(vec![Stmt::Nop(4)]));
+    assert_eq!(
+      parse_line("loop: NOP", 1),
+      Ok(vec![Stmt::Label("loop".to_string(), 1), Stmt::Nop(1)])
+    );
+    assert_eq!(
+      parse_line("NOP 1", 2),
+      Err(ParseError::UnsupportedSyntax(2, Span::new(4, 5)))
+    );
+  }
+
+  #[test]
+  fn test_parse_program() {
+    let outcome = parse_program("LOAD =1\nSTORE =2\n# comment\nADD 1\nLOAD\nHALT");
+    assert_eq!(
+      outcome.statements,
+      vec![
+        Stmt::Load(Value::Pure(1), 1),
+        Stmt::Add(Value::Register(RegisterValue::Direct(1)), 4),
+        Stmt::Halt(6),
+      ]
+    );
+    assert_eq!(
+      outcome
+        .errors
+        .iter()
+        .map(ParseError::line)
+        .collect::<Vec<_>>(),
+      vec![2, 5]
+    );
+    assert!(parse_program("HALT").is_ok());
+  }
+
+  #[test]
+  fn test_parse_all_collects_every_error() {
+    let (stmts, errors) = parse_all("LOAD =1\nSTORE a\nADD =2\nFOO 1\nHALT");
+
+    assert_eq!(
+      stmts,
+      vec![
+        Stmt::Load(Value::Pure(1), 1),
+        Stmt::Add(Value::Pure(2), 3),
+        Stmt::Halt(5),
+      ]
+    );
+    assert_eq!(
+      errors,
+      vec![
+        ParseError::not_valid_argument(2, Span::new(6, 7), "a"),
+        ParseError::UnsupportedOpcode(4, Span::new(0, 3), "FOO".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_format_source() {
+    let source = "
+      start:   load   =1   # initial value
+        Add 2
+
+
+      loop: jz    end
+      # comment only
+      jmp loop
+      end:
+      halt
+    ";
+
+    assert_eq!(
+      format_source(source),
+      Ok(
+        "start:\nLOAD =1 # initial value\nADD 2\n\nloop:\nJZERO end\n# comment only\nJUMP loop\nend:\nHALT\n"
+          .to_string()
+      )
+    );
+  }
+
+  #[test]
+  fn test_format_source_reports_errors() {
+    assert_eq!(
+      format_source("LOAD =1\nstore =2"),
+      Err(ParseError::pure_argument_not_allowed(
+        2,
+        Span::new(6, 8),
+        "=2"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_validate_labels() {
+    let stmts: Vec<Stmt> = parse("JUMP end\nloop: JZ missing\nJGTZ loop\nJMP gone\nend: HALT")
+      .collect::<Result<_, _>>()
+      .unwrap();
+
+    assert_eq!(
+      validate_labels(&stmts),
+      Err(vec![
+        ParseError::UnknownLabel(2, "missing".to_string()),
+        ParseError::UnknownLabel(4, "gone".to_string()),
+      ])
+    );
+    assert_eq!(
+      validate_labels(&stmts[..1]),
+      Err(vec![ParseError::UnknownLabel(1, "end".to_string())])
+    );
+    assert_eq!(validate_labels(&[]), Ok(()));
+  }
+
+  #[test]
+  fn test_labels_are_case_sensitive_by_default() {
+    let stmts: Vec<Stmt> = parse("Loop: JUMP LOOP").collect::<Result<_, _>>().unwrap();
+
+    assert_eq!(
+      stmts,
+      vec![
+        Stmt::Label("Loop".to_string(), 1),
+        Stmt::Jump(Label::new("LOOP".to_string()), 1),
+      ]
+    );
+    assert!(validate_labels(&stmts).is_err());
+  }
+
+  #[test]
+  fn test_case_insensitive_labels() {
+    let options = ParserOptions {
+      case_insensitive_labels: true,
+      ..Default::default()
+    };
+    let stmts: Vec<Stmt> = parse_with_options("Loop: JUMP LOOP", options)
+      .collect::<Result<_, _>>()
+      .unwrap();
+
+    assert_eq!(
+      stmts,
+      vec![
+        Stmt::Label("loop".to_string(), 1),
+        Stmt::Jump(Label::new("loop".to_string()), 1),
+      ]
+    );
+    assert_eq!(validate_labels(&stmts), Ok(()));
+  }
+
+  #[test]
+  fn test_unicode_labels() {
+    let options = ParserOptions {
+      unicode_labels: true,
+      ..Default::default()
+    };
+
+    assert_eq!(
+      parse_line_with_options("фывфыфыв:", 0, &options),
+      Ok(vec![Stmt::Label("фывфыфыв".to_string(), 0)])
+    );
+    assert_eq!(
+      parse_line_with_options("JUMP schleife_2", 0, &options),
+      Ok(vec![Stmt::Jump(Label::new("schleife_2".to_string()), 0)])
+    );
+    assert_eq!(
+      parse_line_with_options("JUMP 週末", 0, &options),
+      Ok(vec![Stmt::Jump(Label::new("週末".to_string()), 0)])
+    );
+    assert_eq!(
+      parse_line_with_options("1метка:", 0, &options),
+      Err(ParseError::LabelIsNotValid(0, Span::new(0, 12)))
+    );
+    assert_eq!(
+      parse_line("фывфыфыв:", 0),
+      Err(ParseError::LabelIsNotValid(0, Span::new(0, 17)))
+    );
+  }
+
+  #[test]
+  fn test_parse_with_comments() {
+    let options = ParserOptions::default();
+    let stmts: Vec<_> =
+      parse_with_comments("LOAD =1 #  one  \nHALT\n# only a comment", options.clone()).collect();
+    assert_eq!(
+      stmts,
+      vec![
+        Ok((Stmt::Load(Value::Pure(1), 1), Some("one".to_string()))),
+        Ok((Stmt::Halt(2), None)),
+      ]
+    );
+
+    let stmts: Vec<_> = parse_with_comments("LOAD =1; HALT # stop", options).collect();
+    assert_eq!(
+      stmts,
+      vec![
+        Ok((Stmt::Load(Value::Pure(1), 1), None)),
+        Ok((Stmt::Halt(1), Some("stop".to_string()))),
+      ]
+    );
+
+    let options = ParserOptions {
+      comment_chars: vec![';'],
+      ..ParserOptions::default()
+    };
+    let stmts: Vec<_> = parse_with_comments("HALT ;; stop # here", options).collect();
+    assert_eq!(
+      stmts,
+      vec![Ok((Stmt::Halt(1), Some("; stop # here".to_string())))]
+    );
+  }
+
+  #[test]
+  fn test_parse_output_without_argument() {
+    let accumulator = Value::Register(RegisterValue::Direct(0));
+    assert_eq!(
+      parse_line("OUTPUT", 1),
+      Ok(vec![Stmt::Output(accumulator.clone(), 1)])
+    );
+    assert_eq!(
+      parse_line("write", 2),
+      Ok(vec![Stmt::Output(accumulator, 2)])
+    );
+    assert_eq!(
+      parse_line("WRITE =3", 3),
+      Ok(vec![Stmt::Output(Value::Pure(3), 3)])
+    );
+  }
+
+  #[test]
+  fn test_reserved_label_name() {
+    assert_eq!(
+      parse_line("HALT:", 1),
+      Ok(vec![Stmt::Label("HALT".to_string(), 1)])
+    );
+
+    let options = ParserOptions {
+      reject_reserved_label_names: true,
+      ..ParserOptions::default()
+    };
+    assert_eq!(
+      parse_line_with_options("HALT:", 1, &options),
+      Err(ParseError::ReservedLabelName(
+        1,
+        Span::new(0, 5),
+        "HALT".to_string()
+      ))
+    );
+    assert_eq!(
+      parse_line_with_options("LABEL load", 2, &options),
+      Err(ParseError::ReservedLabelName(
+        2,
+        Span::new(6, 10),
+        "load".to_string()
+      ))
+    );
+
+    let outcome = parse_program("LOAD =1\n  halt: HALT\nend: HALT");
+    assert!(outcome.is_ok());
+    assert_eq!(outcome.statements.len(), 5);
+    assert_eq!(
+      outcome.warnings,
+      vec![ParseError::ReservedLabelName(
+        2,
+        Span::new(0, 5),
+        "halt".to_string()
+      )]
+    );
+
+    let outcome = parse_program("NOP\nhalt: NOP; LABEL load");
+    assert_eq!(outcome.statements.len(), 4);
+    assert_eq!(
+      outcome.warnings,
+      vec![
+        ParseError::ReservedLabelName(2, Span::new(0, 5), "halt".to_string()),
+        ParseError::ReservedLabelName(2, Span::new(17, 21), "load".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_parse_alias() {
+    assert_eq!(
+      parse_line("alias done end", 1),
+      Ok(vec![Stmt::Alias(
+        "done".to_string(),
+        Label::new("end".to_string()),
+        1
+      )])
+    );
+    assert_eq!(
+      parse_line("ALIAS done", 2),
+      Err(ParseError::ArgumentIsRequired(2, Span::new(0, 5)))
+    );
+    assert_eq!(
+      parse_line("ALIAS done 1end", 3),
+      Err(ParseError::LabelIsNotValid(3, Span::new(11, 15)))
+    );
+    assert_eq!(
+      parse_line("ALIAS a b c", 4),
+      Err(ParseError::UnsupportedSyntax(4, Span::new(10, 11)))
+    );
+  }
+
+  #[test]
+  fn test_parse_collect() {
+    let stmts = parse_collect("LOAD =1\n\n# comment\nloop: HALT").unwrap();
+    assert_eq!(
+      stmts,
+      vec![
+        Stmt::Load(Value::Pure(1), 1),
+        Stmt::Label("loop".to_string(), 4),
+        Stmt::Halt(4),
+      ]
+    );
+    assert!(stmts.capacity() >= 4);
+
+    assert_eq!(
+      parse_collect("LOAD\nFOO 1"),
+      Err(ParseError::ArgumentIsRequired(1, Span::new(0, 4)))
+    );
+  }
+
+  #[test]
+  fn test_whitespace_and_line_endings() {
+    let load = Stmt::Load(Value::Pure(5), 1);
+    assert_eq!(parse_collect("LOAD\t=5\r"), Ok(vec![load.clone()]));
+    assert_eq!(parse_collect("\tLOAD \t  =5"), Ok(vec![load.clone()]));
+    assert_eq!(parse_collect("LOAD     =5   "), Ok(vec![load]));
+
+    assert_eq!(
+      parse_collect("loop:\tLOAD =1\r\nJUMP\tloop # comment\r\n\r\nHALT\r\n"),
+      Ok(vec![
+        Stmt::Label("loop".to_string(), 1),
+        Stmt::Load(Value::Pure(1), 1),
+        Stmt::Jump(Label::new("loop".to_string()), 2),
+        Stmt::Halt(4),
+      ])
+    );
+    assert_eq!(
+      parse_spanned("NOP\r\nHALT\r\n").collect::<Vec<_>>(),
+      vec![Ok((Stmt::Nop(1), 0..3)), Ok((Stmt::Halt(2), 5..9))]
+    );
+    assert_eq!(
+      parse_with_comments("HALT # done\r\n", ParserOptions::default()).collect::<Vec<_>>(),
+      vec![Ok((Stmt::Halt(1), Some("done".to_string())))]
+    );
+  }
+
+  #[test]
+  fn test_parse_one() {
+    assert_eq!(parse_one("  halt  "), Ok(Some(Stmt::Halt(0))));
+    assert_eq!(
+      parse_one("JUMP end # go"),
+      Ok(Some(Stmt::Jump(Label::new("end".to_string()), 0)))
+    );
+    assert_eq!(parse_one(""), Ok(None));
+    assert_eq!(
+      parse_one("loop: LOAD =1"),
+      Err(ParseError::UnsupportedSyntax(0, Span::new(6, 13)))
+    );
+    assert_eq!(
+      parse_one("NOP; HALT; NOP"),
+      Err(ParseError::UnsupportedSyntax(0, Span::new(5, 14)))
+    );
+    assert_eq!(
+      parse_one("FOO"),
+      Err(ParseError::UnsupportedOpcode(
+        0,
+        Span::new(0, 3),
+        "FOO".to_string()
+      ))
+    );
+  }
+
+  #[test]
+  fn test_argument_value_out_of_range() {
+    let out_of_range = |span, token| {
+      Err(ParseError::invalid_argument(
+        0,
+        span,
+        InvalidArgument::ArgumentValueOutOfRange,
+        token,
+      ))
+    };
+    assert_eq!(
+      parse_line("LOAD =99999999999999999999", 0),
+      out_of_range(Span::new(5, 26), "=99999999999999999999")
+    );
+    assert_eq!(
+      parse_line("LOAD =-0x8000000000000001", 0),
+      out_of_range(Span::new(5, 25), "=-0x8000000000000001")
+    );
+    assert_eq!(
+      parse_line("STORE 99999999999999999999999999999999999999999", 0),
+      out_of_range(
+        Span::new(6, 47),
+        "99999999999999999999999999999999999999999"
+      )
+    );
+    assert_eq!(
+      parse_line("LOAD *-2", 0),
+      out_of_range(Span::new(5, 8), "*-2")
+    );
+
+    assert_eq!(
+      parse_line("LOAD =12ab", 0),
+      Err(ParseError::argument_value_must_be_numeric(
+        0,
+        Span::new(5, 10),
+        "=12ab"
+      ))
+    );
+    assert_eq!(
+      parse_line("LOAD =99999999999999999999", 0)
+        .unwrap_err()
+        .to_string(),
+      "line 0: argument value is out of range, found '=99999999999999999999'"
+    );
+  }
+
+  #[test]
+  fn test_parse_inc_dec() {
+    assert_eq!(
+      parse_line("INC 3", 1),
+      Ok(vec![Stmt::Inc(RegisterValue::Direct(3), 1)])
+    );
+    assert_eq!(
+      parse_line("dec *2", 1),
+      Ok(vec![Stmt::Dec(RegisterValue::Indirect(2), 1)])
+    );
+    assert_eq!(
+      parse_line("INC =1", 1),
+      Err(ParseError::pure_argument_not_allowed(
+        1,
+        Span::new(4, 6),
+        "=1"
+      ))
+    );
+    assert_eq!(
+      parse_line("DEC", 1),
+      Err(ParseError::ArgumentIsRequired(1, Span::new(0, 3)))
+    );
+  }
+
+  #[test]
+  fn test_parse_swap() {
+    assert_eq!(
+      parse_line("SWAP 2", 1),
+      Ok(vec![Stmt::Swap(RegisterValue::Direct(2), 1)])
+    );
+    assert_eq!(
+      parse_line("swap *4", 2),
+      Ok(vec![Stmt::Swap(RegisterValue::Indirect(4), 2)])
+    );
+  }
+
+  #[test]
+  fn test_unsupported_opcode_keeps_case() {
+    assert_eq!(
+      parse_line("lod =1", 3),
+      Err(ParseError::UnsupportedOpcode(
+        3,
+        Span::new(0, 3),
+        "lod".to_string()
+      ))
+    );
+  }
+
+  #[test]
+  fn test_empty_label() {
+    assert_eq!(
+      parse_line(":", 1),
+      Err(ParseError::EmptyLabel(1, Span::new(0, 1)))
+    );
+    assert_eq!(
+      parse_line("  : LOAD =1", 2),
+      Err(ParseError::EmptyLabel(2, Span::new(2, 3)))
+    );
+    assert_eq!(
+      parse_line("1abc:", 3),
+      Err(ParseError::LabelIsNotValid(3, Span::new(0, 5)))
+    );
+  }
+
+  #[test]
+  fn test_opcode_aliases() {
+    let mut options = ParserOptions::default();
+    options
+      .opcode_aliases
+      .insert("PRINT".to_string(), "OUTPUT".to_string());
+    options
+      .opcode_aliases
+      .insert("SHOUT".to_string(), "YELL".to_string());
+
+    assert_eq!(
+      parse_line_with_options("print =5", 1, &options),
+      Ok(vec![Stmt::Output(Value::Pure(5), 1)])
+    );
+    assert_eq!(
+      parse_line_with_options("WRITE 2", 2, &options),
+      Ok(vec![Stmt::Output(
+        Value::Register(RegisterValue::Direct(2)),
+        2
+      )])
+    );
+    assert_eq!(
+      parse_line_with_options("SHOUT 1", 3, &options),
+      Err(ParseError::UnsupportedOpcode(
+        3,
+        Span::new(0, 5),
+        "SHOUT".to_string()
+      ))
+    );
+    assert_eq!(
+      parse_line("PRINT =5", 4),
+      Err(ParseError::UnsupportedOpcode(
+        4,
+        Span::new(0, 5),
+        "PRINT".to_string()
+      ))
+    );
+  }
+
+  #[test]
+  fn test_try_parse_line() {
+    let options = ParserOptions::default();
+    let (result, lex) = try_parse_line("loop: ADD =2 # step", 3, &options);
+    assert_eq!(
+      result,
+      Ok(vec![
+        Stmt::Label("loop".to_string(), 3),
+        Stmt::Add(Value::Pure(2), 3),
+      ])
+    );
+    assert_eq!(
+      lex,
+      LineLex {
+        label: Some(Span::new(0, 5)),
+        opcode: Some(Span::new(6, 9)),
+        argument: Some(Span::new(10, 12)),
+        comment: Some(Span::new(13, 19)),
+        tokens: 3,
+      }
+    );
+
+    let (result, lex) = try_parse_line("FOO 1 2; HALT", 4, &options);
+    assert!(result.is_err());
+    assert_eq!(
+      lex,
+      LineLex {
+        label: None,
+        opcode: Some(Span::new(0, 3)),
+        argument: Some(Span::new(4, 7)),
+        comment: None,
+        tokens: 3,
+      }
+    );
+
+    assert_eq!(
+      try_parse_line("", 5, &options),
+      (Ok(vec![]), LineLex::default())
+    );
+
+    let options = ParserOptions {
+      comment_chars: vec![';'],
+      ..ParserOptions::default()
+    };
+    let (result, lex) = try_parse_line("HALT ;; stop # here", 6, &options);
+    assert_eq!(result, Ok(vec![Stmt::Halt(6)]));
+    assert_eq!(lex.opcode, Some(Span::new(0, 4)));
+    assert_eq!(lex.comment, Some(Span::new(5, 19)));
+    assert_eq!(lex.tokens, 1);
+  }
+
+  #[test]
+  fn test_statement_spans_comment_chars() {
+    assert_eq!(
+      statement_spans("a: NOP % b: HALT", &['%']),
+      vec![Span::new(0, 2), Span::new(3, 6)]
+    );
+    assert_eq!(
+      statement_spans("a: NOP # b: HALT", &['%']),
+      vec![Span::new(0, 2), Span::new(3, 16)]
+    );
+  }
+
+  #[test]
+  fn test_parse_halt_code() {
+    assert_eq!(parse_line("HALT", 1), Ok(vec![Stmt::Halt(1)]));
+    assert_eq!(parse_line("halt 1", 2), Ok(vec![Stmt::HaltCode(1, 2)]));
+    assert_eq!(
+      parse_line("HALT -0x10", 3),
+      Ok(vec![Stmt::HaltCode(-16, 3)])
+    );
+    assert_eq!(
+      parse_line("HALT =1", 4),
+      Err(ParseError::argument_value_must_be_numeric(
+        4,
+        Span::new(5, 7),
+        "=1"
+      ))
+    );
+    assert_eq!(
+      parse_line("HALT 1 2", 5),
+      Err(ParseError::UnsupportedSyntax(5, Span::new(7, 8)))
+    );
+  }
+
+  #[test]
+  fn test_parse_with_line_offset() {
+    let mut map = SourceMap::default();
+    let first = "LOAD =1\n\n# comment\nSTORE 1";
+    let second = "ADD 1\nSTORE x";
+
+    let first_start = map.add("first.ram", first);
+    let second_start = map.add("second.ram", second);
+    assert_eq!((first_start, second_start), (1, 5));
+
+    let stmts: Vec<_> = parse_with_line_offset(first, first_start).collect();
+    assert_eq!(stmts, parse(first).collect::<Vec<_>>());
+
+    let stmts: Vec<_> = parse_with_line_offset(second, second_start).collect();
+    assert_eq!(
+      stmts[0],
+      Ok(Stmt::Add(Value::Register(RegisterValue::Direct(1)), 5))
+    );
+    let error = stmts[1].clone().unwrap_err();
+    assert_eq!(error.line(), 6);
+    assert_eq!(map.lookup(error.line()), Some(("second.ram", 2)));
+
+    assert_eq!(map.lookup(4), Some(("first.ram", 4)));
+    assert_eq!(map.lookup(0), None);
+    assert_eq!(map.lookup(7), None);
+  }
+
+  #[test]
+  fn test_parse_with_limits() {
+    let limits = ParseLimits {
+      max_lines: 3,
+      max_line_len: 8,
+    };
+
+    assert_eq!(
+      parse_with_limits("LOAD =12\nNOP\nHALT", limits),
+      Ok(vec![
+        Stmt::Load(Value::Pure(12), 1),
+        Stmt::Nop(2),
+        Stmt::Halt(3)
+      ])
+    );
+    assert_eq!(
+      parse_with_limits("NOP\nNOP\nNOP\nHALT", limits),
+      Err(ParseError::TooManyLines(4))
+    );
+    assert_eq!(
+      parse_with_limits("NOP\nLOAD =123\nHALT", limits),
+      Err(ParseError::LineTooLong(2, 9))
+    );
+    // The limits are checked before parsing.
+    assert_eq!(
+      parse_with_limits("FOO\nNOP\nNOP\nNOP", limits),
+      Err(ParseError::TooManyLines(4))
+    );
+    assert!(parse_with_limits("FOO", ParseLimits::default()).is_err());
+  }
+
+  #[test]
+  fn test_parse_with_repeats() {
+    let source =
+      "LOAD =1\nrepeat 3\n  ADD =1\n  REPEAT 2 # nested\n    WRITE 0\n  ENDREPEAT\nENDREPEAT\nHALT";
+    let stmts = parse_with_repeats(source, &ParserOptions::default(), 11).unwrap();
+    assert_eq!(stmts.len(), 1 + 3 * (1 + 2) + 1);
+    assert_eq!(stmts[1], Stmt::Add(Value::Pure(1), 3));
+    assert_eq!(
+      stmts[2],
+      Stmt::Output(Value::Register(RegisterValue::Direct(0)), 5)
+    );
+    assert_eq!(stmts[10], Stmt::Halt(8));
+
+    #[cfg(feature = "std")]
+    {
+      use crate::{io::VecOutput, program::Program, ram::Ram};
+
+      let output = VecOutput::default();
+      let mut ram = Ram::new(
+        Program::try_from(stmts).unwrap(),
+        Box::new(std::io::empty()),
+        Box::new(output.clone()),
+      );
+      ram.run().unwrap();
+      assert_eq!(output.values(), vec![2, 2, 3, 3, 4, 4]);
+    }
+  }
+
+  #[test]
+  fn test_parse_with_repeats_errors() {
+    let options = ParserOptions::default();
+    assert_eq!(
+      parse_with_repeats("REPEAT 2\nloop: NOP\nENDREPEAT", &options, 100),
+      Err(ParseError::UnsupportedSyntax(2, Span::new(0, 5)))
+    );
+    assert_eq!(
+      parse_with_repeats("NOP\nENDREPEAT", &options, 100),
+      Err(ParseError::UnsupportedSyntax(2, Span::new(0, 9)))
+    );
+    assert_eq!(
+      parse_with_repeats("NOP\nREPEAT 2\nNOP", &options, 100),
+      Err(ParseError::UnsupportedSyntax(2, Span::new(0, 6)))
+    );
+    assert_eq!(
+      parse_with_repeats("REPEAT", &options, 100),
+      Err(ParseError::ArgumentIsRequired(1, Span::new(0, 6)))
+    );
+    assert_eq!(
+      parse_with_repeats("REPEAT -1\nENDREPEAT", &options, 100),
+      Err(ParseError::invalid_argument(
+        1,
+        Span::new(7, 9),
+        InvalidArgument::ArgumentValueOutOfRange,
+        "-1"
+      ))
+    );
+  }
+
+  #[test]
+  fn test_parse_with_repeats_limit() {
+    let options = ParserOptions::default();
+    let source = "REPEAT 1000000\nREPEAT 1000000\nNOP\nENDREPEAT\nENDREPEAT";
+    assert_eq!(
+      parse_with_repeats(source, &options, 1_000_000),
+      Err(ParseError::TooManyStatements(5))
+    );
+    let source = "REPEAT 18446744073709551615\nREPEAT 2\nNOP\nENDREPEAT\nENDREPEAT";
+    assert_eq!(
+      parse_with_repeats(source, &options, usize::MAX),
+      Err(ParseError::TooManyStatements(5))
+    );
+    assert_eq!(
+      parse_with_repeats("NOP\nNOP\nNOP", &options, 2),
+      Err(ParseError::TooManyStatements(3))
+    );
+    assert_eq!(
+      parse_with_repeats("REPEAT 3\nNOP\nENDREPEAT\nHALT", &options, 4).map(|s| s.len()),
+      Ok(4)
+    );
+    let source = "REPEAT 18446744073709551615\nENDREPEAT\nHALT";
+    assert_eq!(
+      parse_with_repeats(source, &options, 100).map(|s| s.len()),
+      Ok(1)
+    );
+    let source = "REPEAT 18446744073709551615\nREPEAT 0\nNOP\nENDREPEAT\nENDREPEAT\nHALT";
+    assert_eq!(
+      parse_with_repeats(source, &options, 100).map(|s| s.len()),
+      Ok(1)
+    );
+  }
+
+  #[test]
+  fn test_parse_with_repeats_comment_chars() {
+    let options = ParserOptions {
+      comment_chars: vec!['%'],
+      ..ParserOptions::default()
+    };
+    let stmts = parse_with_repeats("REPEAT 2 % twice\nNOP % nothing\nENDREPEAT %", &options, 10);
+    assert_eq!(stmts, Ok(vec![Stmt::Nop(2), Stmt::Nop(2)]));
+  }
+
+  #[test]
+  fn test_parse_label_address() {
+    assert_eq!(
+      parse_line("LOAD =@end", 1),
+      Ok(vec![Stmt::Load(
+        Value::LabelAddr(Label::new("end".to_string())),
+        1
+      )])
+    );
+    assert_eq!(
+      parse_line("ADD =@end", 2).unwrap()[0].to_string(),
+      "ADD =@end"
+    );
+    assert_eq!(
+      parse_line("LOAD =@1x", 3),
+      Err(ParseError::invalid_argument(
+        3,
+        Span::new(5, 9),
+        InvalidArgument::LabelIsNotValid,
+        "=@1x"
+      ))
+    );
+    assert_eq!(
+      validate_labels(&parse_line("WRITE =@nowhere", 4).unwrap()),
+      Err(vec![ParseError::UnknownLabel(4, "nowhere".to_string())])
+    );
+  }
+
+  #[test]
+  fn test_every_opcode_is_handled() {
+    use crate::errors::ParseErrorKind;
+
+    for opcode in OPCODES {
+      for arguments in ["", " 1", " =1", " *1", " a", " a b"] {
+        let source = format!("{opcode}{arguments}");
+        for source in [source.clone(), source.to_lowercase()] {
+          if let Err(error) = parse_line(&source, 1) {
+            assert_ne!(error.kind(), ParseErrorKind::UnsupportedOpcode, "{source}");
+          }
+        }
+      }
+    }
+  }
 }
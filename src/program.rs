@@ -2,11 +2,12 @@
 //! the instructions and labels of the program, and provides methods for creating,
 //! modifying, and querying the program structure.
 use rustc_hash::FxHashMap as HashMap;
+use rustc_hash::FxHashSet as HashSet;
 
 use crate::{
   errors::ParseError,
   parser,
-  stmt::{Label, Stmt},
+  stmt::{Label, Stmt, Value},
 };
 
 /// Represents a program code.
@@ -15,6 +16,7 @@ use crate::{
 /// and provides methods for creating, modifying, and querying the program
 /// structure.
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Program {
   /// Instructions of the program.
   pub instructions: Vec<Stmt>,
@@ -25,7 +27,8 @@ pub struct Program {
 impl Program {
   /// Creates a new [`Program`] from the vector of [`Stmt`].
   ///
-  /// This method initializes the labels in the program.
+  /// This method initializes the labels in the program without validating jump targets.
+  /// Use [`Program::try_from`] to apply the same validation as [`Program::from_source`].
   pub fn from(instructions: Vec<Stmt>) -> Self {
     let mut p = Program {
       instructions,
@@ -37,18 +40,52 @@ impl Program {
 
   /// Creates a new [`Program`] from the source code.
   ///
-  /// This method parses the source code, creating a [`Program`] with the resulting
-  /// instructions and labels.
-  pub fn from_source(source: &str) -> Result<Program, ParseError> {
-    let stmts: Result<Vec<Stmt>, ParseError> = parser::parse(source).collect();
-    let stmts = stmts?;
+  /// This method parses the source code, creating a ready-to-run [`Program`] with the
+  /// resulting instructions and labels. If any line fails to parse, every parse error is
+  /// returned. Otherwise labels are validated as in [`Program::try_from`].
+  pub fn from_source(source: &str) -> Result<Program, Vec<ParseError>> {
+    let (stmts, errors) = parser::parse_all(source);
+    if !errors.is_empty() {
+      return Err(errors);
+    }
+
+    Program::try_from(stmts)
+  }
 
-    Ok(Program::from(stmts))
+  /// Returns a [`ParseError::UnknownLabel`] for every jump whose target label is not defined,
+  /// in program order, along with the errors of aliases not referring to a label, see
+  /// [`parser::validate_labels`].
+  pub fn unknown_labels(&self) -> Vec<ParseError> {
+    parser::validate_labels(&self.instructions)
+      .err()
+      .unwrap_or_default()
+  }
+
+  /// Returns a [`ParseError::DuplicateLabel`] for every definition of a label or alias that
+  /// was already defined earlier, in program order.
+  pub fn duplicate_labels(&self) -> Vec<ParseError> {
+    let mut defined = HashSet::default();
+    self
+      .instructions
+      .iter()
+      .filter_map(|stmt| match stmt {
+        Stmt::Label(label, line) | Stmt::Alias(label, _, line)
+          if !defined.insert(label.as_str()) =>
+        {
+          Some(ParseError::DuplicateLabel(*line, label.clone()))
+        }
+        _ => None,
+      })
+      .collect()
   }
 
   /// Initializes labels of the program.
   ///
   /// This method updates the internal label mapping based on the current instructions.
+  /// Aliases are mapped to the index of the label they refer to; aliases that don't
+  /// eventually refer to a label are left out. Label addresses (`=@name`) of defined labels
+  /// are then replaced with the index of the instruction they label. Addresses resolved
+  /// before are kept, so they are not updated when instructions are injected or removed.
   #[inline]
   pub fn init_labels(&mut self) {
     self.labels.clear();
@@ -57,8 +94,37 @@ impl Program {
         self.labels.insert(label.clone(), index);
       }
     }
-  }
 
+    // Every pass resolves at least one more alias of a chain, so this ends once a pass
+    // resolves nothing.
+    loop {
+      let mut resolved = false;
+      for op in &self.instructions {
+        if let Stmt::Alias(name, target, _) = op {
+          if self.labels.contains_key(name) {
+            continue;
+          }
+          if let Some(&index) = self.labels.get(target.get()) {
+            self.labels.insert(name.clone(), index);
+            resolved = true;
+          }
+        }
+      }
+      if !resolved {
+        break;
+      }
+    }
+
+    for stmt in &mut self.instructions {
+      if let Some(value) = stmt.value_mut() {
+        if let Value::LabelAddr(label) = value {
+          if let Some(&index) = self.labels.get(label.get()) {
+            *value = Value::Pure(index as isize);
+          }
+        }
+      }
+    }
+  }
 
   /// Returns the instruction at the given index.
   ///
@@ -68,6 +134,12 @@ impl Program {
     self.instructions.get(index)
   }
 
+  /// Returns an iterator over the instructions of the program, in order.
+  #[inline]
+  pub fn iter(&self) -> core::slice::Iter<'_, Stmt> {
+    self.instructions.iter()
+  }
+
   /// Decodes the label into the instruction index.
   ///
   /// If the label is not found, returns `None`.
@@ -76,6 +148,109 @@ impl Program {
     self.labels.get(label.get()).copied()
   }
 
+  /// Returns every label and the index of the instruction it is defined at, ordered by index
+  /// and then by name. Aliases are listed with the index of the label they refer to.
+  pub fn labels(&self) -> impl Iterator<Item = (&str, usize)> {
+    let mut labels: Vec<_> = self
+      .labels
+      .iter()
+      .map(|(label, index)| (label.as_str(), *index))
+      .collect();
+    labels.sort_by_key(|(label, index)| (*index, *label));
+    labels.into_iter()
+  }
+
+  /// Returns the indices of the instructions that can never be executed, in ascending order.
+  ///
+  /// Reachability is computed from the first instruction by following fall-through and jump
+  /// targets. An indirect jump may land anywhere, so once one is reachable every instruction
+  /// is considered reachable. This is purely analytical and doesn't change execution.
+  pub fn unreachable_statements(&self) -> Vec<usize> {
+    let len = self.instructions.len();
+    let mut reachable = vec![false; len];
+    let mut pending = vec![0];
+
+    while let Some(index) = pending.pop() {
+      if index >= len || reachable[index] {
+        continue;
+      }
+      reachable[index] = true;
+
+      match &self.instructions[index] {
+        Stmt::Halt(_) | Stmt::HaltCode(..) => {}
+        Stmt::Jump(label, _) => pending.extend(self.decode_label(label)),
+        Stmt::JumpIfZero(label, _)
+        | Stmt::JumpGreaterZero(label, _)
+        | Stmt::JumpLessZero(label, _)
+        | Stmt::JumpNotZero(label, _) => {
+          pending.extend(self.decode_label(label));
+          pending.push(index + 1);
+        }
+        Stmt::JumpIndirect(..) => return Vec::new(),
+        _ => pending.push(index + 1),
+      }
+    }
+
+    (0..len).filter(|index| !reachable[*index]).collect()
+  }
+
+  /// Returns the indices of the label definitions that start a trivial infinite loop, in
+  /// ascending order.
+  ///
+  /// A loop is trivial when the only instruction reached from the label is an unconditional
+  /// `JUMP` back to it, e.g. `a: JUMP a`; other label and alias definitions and `NOP`s in
+  /// between are skipped. This is a best-effort lint rather than a termination analysis:
+  /// loops with any other instruction are not reported, and neither is whether the loop is
+  /// reachable from the start of the program.
+  pub fn find_trivial_infinite_loops(&self) -> Vec<usize> {
+    let mut loops: Vec<usize> = self
+      .instructions
+      .iter()
+      .enumerate()
+      .filter_map(|(index, stmt)| match stmt {
+        Stmt::Jump(label, _) => {
+          let target = self.decode_label(label)?;
+          let skipped = self.instructions.get(target..index)?;
+          skipped
+            .iter()
+            .all(|stmt| matches!(stmt, Stmt::Label(..) | Stmt::Alias(..) | Stmt::Nop(_)))
+            .then_some(target)
+        }
+        _ => None,
+      })
+      .collect();
+    loops.sort_unstable();
+    loops.dedup();
+    loops
+  }
+
+  /// Returns a listing of the program with the address of every instruction, one per line,
+  /// e.g. `0002  loop: LOAD =1`.
+  ///
+  /// Label definitions are printed in front of the instruction that follows them, with the
+  /// address of the label, which is where jumps to it land. Unlike
+  /// [`format_source`](parser::format_source) the listing is not meant to be parsed again.
+  pub fn listing(&self) -> String {
+    let mut listing = String::new();
+    let mut pending: Option<(usize, String)> = None;
+
+    for (index, stmt) in self.instructions.iter().enumerate() {
+      let (address, line) = pending.get_or_insert_with(|| (index, String::new()));
+      line.push_str(&stmt.to_string());
+      if let Stmt::Label(..) = stmt {
+        line.push(' ');
+      } else {
+        listing.push_str(&format!("{address:04}  {line}\n"));
+        pending = None;
+      }
+    }
+    if let Some((address, line)) = pending {
+      listing.push_str(&format!("{address:04}  {}\n", line.trim_end()));
+    }
+
+    listing
+  }
+
   /// Injects an instruction at given index.
   #[inline]
   pub fn inject_instruction(&mut self, instruction: Stmt, index: usize) {
@@ -97,7 +272,7 @@ impl Program {
     T: IntoIterator<Item = Stmt>,
   {
     let tail = self.instructions.split_off(index);
-    self.instructions.extend(instructions.into_iter());
+    self.instructions.extend(instructions);
     self.instructions.extend(tail);
     self.init_labels();
   }
@@ -117,3 +292,235 @@ impl Program {
     self.init_labels();
   }
 }
+
+impl<'a> IntoIterator for &'a Program {
+  type Item = &'a Stmt;
+  type IntoIter = core::slice::Iter<'a, Stmt>;
+
+  #[inline]
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl TryFrom<Vec<Stmt>> for Program {
+  type Error = Vec<ParseError>;
+
+  /// Creates a new [`Program`] from the vector of [`Stmt`], resolving labels once.
+  ///
+  /// Unlike [`Program::from`], every jump target is checked to exist, so a jump to an
+  /// undefined label is rejected here instead of at execution time, and every label must be
+  /// defined only once. The error lists every unknown and duplicate label, ordered by line.
+  fn try_from(instructions: Vec<Stmt>) -> Result<Self, Self::Error> {
+    let program = Program::from(instructions);
+    let mut errors = program.duplicate_labels();
+    errors.extend(program.unknown_labels());
+    errors.sort_by_key(ParseError::line);
+    if errors.is_empty() {
+      Ok(program)
+    } else {
+      Err(errors)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::errors::ParseErrorKind;
+  use crate::stmt::Value;
+
+  #[test]
+  fn test_labels_are_resolved() {
+    let program = Program::try_from(vec![
+      Stmt::Label("start".to_string(), 1),
+      Stmt::Load(Value::Pure(1), 2),
+      Stmt::Label("end".to_string(), 3),
+      Stmt::Jump(Label::new("start".to_string()), 4),
+    ])
+    .unwrap();
+
+    assert_eq!(
+      program.decode_label(&Label::new("start".to_string())),
+      Some(0)
+    );
+    assert_eq!(
+      program.decode_label(&Label::new("end".to_string())),
+      Some(2)
+    );
+    assert_eq!(
+      program.get(3),
+      Some(&Stmt::Jump(Label::new("start".to_string()), 4))
+    );
+  }
+
+  #[test]
+  fn test_unknown_labels_are_rejected_at_construction() {
+    let result = Program::try_from(vec![
+      Stmt::Jump(Label::new("nowhere".to_string()), 1),
+      Stmt::Label("here".to_string(), 2),
+      Stmt::JumpIfZero(Label::new("here".to_string()), 3),
+      Stmt::JumpGreaterZero(Label::new("elsewhere".to_string()), 4),
+    ]);
+
+    assert_eq!(
+      result.unwrap_err(),
+      vec![
+        ParseError::UnknownLabel(1, "nowhere".to_string()),
+        ParseError::UnknownLabel(4, "elsewhere".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_from_source_rejects_unknown_label() {
+    let result = Program::from_source("JUMP nowhere\nHALT");
+
+    assert_eq!(
+      result.unwrap_err(),
+      vec![ParseError::UnknownLabel(1, "nowhere".to_string())]
+    );
+  }
+
+  #[test]
+  fn test_from_source() {
+    let program = Program::from_source("start: LOAD =1\nJUMP start").unwrap();
+    assert_eq!(program.instructions.len(), 3);
+    assert_eq!(program.labels.get("start"), Some(&0));
+
+    let result = Program::from_source("LOAD\nHALT\nFOO 1");
+    assert_eq!(
+      result
+        .unwrap_err()
+        .iter()
+        .map(ParseError::kind)
+        .collect::<Vec<_>>(),
+      vec![
+        ParseErrorKind::ArgumentIsRequired,
+        ParseErrorKind::UnsupportedOpcode
+      ]
+    );
+
+    let result = Program::from_source("a: HALT\nJUMP b\na: HALT");
+    assert_eq!(
+      result.unwrap_err(),
+      vec![
+        ParseError::UnknownLabel(2, "b".to_string()),
+        ParseError::DuplicateLabel(3, "a".to_string()),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_unreachable_statements() {
+    let program = Program::from_source(
+      "
+      LOAD =1
+      JZ skip
+      HALT
+      ADD =1
+      STORE 1
+      WRITE 1
+      skip:
+      JUMP end
+      LOAD =2
+      end: HALT
+      ",
+    )
+    .unwrap();
+
+    assert_eq!(program.unreachable_statements(), vec![3, 4, 5, 8]);
+  }
+
+  #[test]
+  fn test_find_trivial_infinite_loops() {
+    let program = Program::from_source(
+      "
+      READ 1
+      LOAD 1
+      loop: SUB =1
+      JGZ loop
+      JZ end
+      stuck: NOP
+      again:
+      JUMP stuck
+      end: JUMP end
+      ",
+    )
+    .unwrap();
+
+    assert_eq!(program.find_trivial_infinite_loops(), vec![6, 10]);
+    assert_eq!(
+      Program::from_source("a: JUMP b\nb: JUMP a")
+        .unwrap()
+        .find_trivial_infinite_loops(),
+      Vec::<usize>::new()
+    );
+  }
+
+  #[test]
+  fn test_indirect_jump_makes_everything_reachable() {
+    let program = Program::from_source("JIND 1\nHALT\nLOAD =1").unwrap();
+
+    assert_eq!(program.unreachable_statements(), Vec::<usize>::new());
+  }
+
+  #[test]
+  fn test_listing() {
+    let program =
+      Program::from_source("LOAD =3\nloop: SUB =1\nJGTZ loop\nWRITE 0\nHALT\nend:").unwrap();
+
+    assert_eq!(
+      program.listing(),
+      "0000  LOAD =3\n0001  loop: SUB =1\n0003  JGTZ loop\n0004  WRITE 0\n0005  HALT\n0006  end:\n"
+    );
+  }
+
+  #[test]
+  fn test_alias() {
+    let program =
+      Program::from_source("JUMP done\nALIAS done finish\nALIAS finish end\nNOP\nend: HALT")
+        .unwrap();
+    assert_eq!(program.decode_label(&Label::from("end")), Some(4));
+    assert_eq!(program.decode_label(&Label::from("finish")), Some(4));
+    assert_eq!(program.decode_label(&Label::from("done")), Some(4));
+
+    assert_eq!(
+      Program::from_source("ALIAS a b\nALIAS b a\nALIAS c missing\nend: HALT").unwrap_err(),
+      vec![
+        ParseError::AliasCycle(1, "a".to_string()),
+        ParseError::AliasCycle(2, "b".to_string()),
+        ParseError::UnknownLabel(3, "missing".to_string()),
+      ]
+    );
+    assert_eq!(
+      Program::from_source("end: HALT\nALIAS end end").unwrap_err(),
+      vec![ParseError::DuplicateLabel(2, "end".to_string())]
+    );
+  }
+
+  #[test]
+  fn test_labels() {
+    let program = Program::from_source("start: LOAD =1\nJZERO end\nJUMP start\nend: HALT").unwrap();
+    assert_eq!(
+      program.labels().collect::<Vec<_>>(),
+      vec![("start", 0), ("end", 4)]
+    );
+  }
+
+  #[test]
+  fn test_iter() {
+    let source = "start: LOAD =1\nWRITE 0\nHALT";
+    let program = Program::from_source(source).unwrap();
+
+    let mut stmts = Vec::new();
+    for stmt in &program {
+      stmts.push(stmt.clone());
+    }
+    assert_eq!(stmts, parser::parse_collect(source).unwrap());
+    assert_eq!(
+      program.iter().collect::<Vec<_>>(),
+      stmts.iter().collect::<Vec<_>>()
+    );
+  }
+}
@@ -5,7 +5,7 @@
 //! The RAM machine is a theoretical model of computation that consists of an
 //! infinite array of memory cells, a finite set of registers, and a program counter.
 //! The [`Ram`] struct holds the program, registers, program counter, line
-//! number, halt state, error state, input reader, and output writer.
+//! number, halt state, error state, input source, and output sink.
 //!
 //! The main functionality includes the following methods:
 //!
@@ -13,7 +13,7 @@
 //! 2. Getting the current state of registers.
 //! 3. Fetching the current instruction.
 //! 4. Running the program.
-//! 5. Executing a single instruction.
+//! 5. Executing a single instruction, reporting a [`StepResult`].
 //! 6. Getting the current error state.
 //! 7. Evaluating a given statement.
 //! 8. Evaluating the current statement.
@@ -27,14 +27,16 @@
 //! It is used to track the RAM machine's state at different points in time. It
 //! can be created from a `Ram` instance or a reference to a `Ram` instance
 //! using the [`From`] trait. It also provides a [`RamState::create_ram()`]
-//! method to create a new [`Ram`] instance from a [`RamState`].
+//! method to create a new [`Ram`] instance from a [`RamState`], while
+//! [`Ram::snapshot()`] and [`Ram::restore()`] capture and rewind a running machine.
+//! [`RamState::diff()`] lists the registers in which two states differ.
 //!
 //! # Examples
 //!
 //! ```
-//! use ram::program::Program;
-//! use ram::ram::Ram;
-//! use ram::stmt::{Stmt, Value};
+//! use ramemu::program::Program;
+//! use ramemu::ram::Ram;
+//! use ramemu::stmt::{Stmt, Value};
 //! use std::io::BufReader;
 //! use std::io::BufWriter;
 //!
@@ -50,58 +52,430 @@
 //! let mut ram = Ram::new(program, Box::new(reader), Box::new(writer));
 //!
 //! ram.run().unwrap();
-//! assert_eq!(ram.get_registers().get(0), 3);
+//! assert_eq!(ram.get_registers().get(0), 4);
 //! ```
 //!
 //! This module enables the creation of a RAM machine and provides the necessary functionalities to execute, debug, and manage its state.
+use std::collections::BTreeSet;
 use std::fmt::Debug;
 use std::fmt::Formatter;
-use std::io::BufRead;
-use std::io::Write;
+use std::time::Duration;
+use std::time::Instant;
 
+use rustc_hash::FxHashMap as HashMap;
+
+use crate::compiled::Compiled;
+use crate::compiled::Op;
+use crate::errors::InputError;
 use crate::errors::InterpretError;
+use crate::errors::SimError;
+use crate::io::Input;
+use crate::io::Output;
+use crate::io::VecInput;
+use crate::io::VecOutput;
 use crate::program::Program;
 use crate::registers::Registers;
 use crate::stmt::RegisterValue;
 use crate::stmt::Stmt;
 use crate::stmt::Value;
 
+/// The outcome of executing a single instruction with [`Ram::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StepResult {
+  /// The instruction was executed and the machine can continue.
+  Running,
+  /// The machine executed `HALT` and stopped.
+  Halted,
+}
+
+/// Decides what reading a register that was never written yields.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegisterInitPolicy {
+  /// Unset registers read as `0`. This is the default, matching the classic RAM model where
+  /// every register initially holds zero.
+  #[default]
+  Zero,
+  /// Reading an unset register fails with [`InterpretError::UninitializedRegister`].
+  Error,
+}
+
+/// Decides what dividing by zero with `DIV` or `MOD` yields.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DivByZeroPolicy {
+  /// Dividing by zero fails with [`InterpretError::DivisionByZero`]. This is the default.
+  #[default]
+  Error,
+  /// Sets the accumulator to [`i64::MAX`] if it is positive, to [`i64::MIN`] if it is
+  /// negative, and to `0` if it is zero.
+  Saturate,
+  /// Sets the accumulator to `0`.
+  Zero,
+}
+
+/// Decides what an arithmetic instruction whose result does not fit into a register yields.
+///
+/// Applies to `ADD`, `SUB`, `MUL`, `POW`, and to `DIV` and `MOD` dividing [`i64::MIN`] by `-1`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OverflowPolicy {
+  /// Wraps around at the boundary of the register type.
+  Wrap,
+  /// Clamps the result to [`i64::MIN`] or [`i64::MAX`].
+  Saturate,
+  /// Fails with [`InterpretError::Overflow`]. This is the default.
+  #[default]
+  Error,
+}
+
+/// Decides what happens when execution reaches the end of the program without executing
+/// `HALT`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndOfProgramPolicy {
+  /// Fails with [`InterpretError::FellOffEnd`]. This is the default, so a missing `HALT` is
+  /// reported instead of silently accepted.
+  #[default]
+  Error,
+  /// Halts the machine as if `HALT` was executed. Halting this way does not count as a step.
+  Halt,
+}
+
+/// A breakpoint added with [`Ram::add_breakpoint`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BreakAt {
+  /// Breaks at the definition of the label, which is where jumps to it land.
+  Label(String),
+  /// Breaks at the instruction with the index.
+  Index(usize),
+}
+
+/// The reason [`Ram::run_to_breakpoint`] stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StopReason {
+  /// The instruction at the index has a breakpoint and was not executed yet.
+  Breakpoint(usize),
+  /// The machine halted.
+  Halted,
+}
+
+/// An event reported by [`Ram::run_yielding`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Event {
+  /// The program wrote the value to the output sink.
+  Output(i64),
+  /// The machine halted.
+  Halted,
+}
+
+/// A single executed instruction recorded by [`Ram::enable_trace`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+  /// The index of the executed instruction in the program.
+  pub index: usize,
+  /// The executed instruction.
+  pub stmt: Stmt,
+  /// The value of the accumulator before the instruction was executed.
+  pub accumulator_before: i64,
+  /// The value of the accumulator after the instruction was executed.
+  pub accumulator_after: i64,
+  /// The index and new value of the register written by `STORE`, `READ`, `INC`, `DEC` or
+  /// `SWAP`, if any.
+  pub register: Option<(usize, i64)>,
+}
+
+/// The step limit used by [`simulate`].
+pub const DEFAULT_MAX_STEPS: usize = 1_000_000;
+
+/// The number of steps between two checks of the elapsed time in [`Ram::run_with_timeout`],
+/// unless changed with [`Ram::with_timeout_check_interval`].
+pub const DEFAULT_TIMEOUT_CHECK_INTERVAL: usize = 1024;
+
+/// Parses `source`, runs it on `inputs` and returns the values it writes.
+///
+/// The program runs with the default policies and a limit of [`DEFAULT_MAX_STEPS`] steps, so
+/// a program that loops forever fails with [`InterpretError::StepLimitExceeded`]. Parse
+/// errors and the runtime error are both reported as a [`SimError`].
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::simulate;
+///
+/// let source = "READ 1\nLOAD 1\nMUL =2\nWRITE 0\nHALT";
+/// assert_eq!(simulate(source, &[21]), Ok(vec![42]));
+/// ```
+pub fn simulate(source: &str, inputs: &[i64]) -> Result<Vec<i64>, SimError> {
+  let output = VecOutput::default();
+  Ram::builder()
+    .with_input(Box::new(VecInput::from(inputs.to_vec())))
+    .with_output(Box::new(output.clone()))
+    .with_max_steps(DEFAULT_MAX_STEPS)
+    .build(Program::from_source(source)?)
+    .run()?;
+  Ok(output.values())
+}
+
+/// A callback registered with [`Ram::watch_register`], called with the old and new value.
+type WatchCallback = Box<dyn FnMut(Option<i64>, i64)>;
+
 /// The [`Ram`] struct represents a Random Access Machine (RAM).
 ///
-/// It holds the program, registers, program counter, line number, halt state, error state, input reader, and output writer.
+/// It holds the program, registers, program counter, line number, halt state, error state, input source, and output sink.
 pub struct Ram {
   program: Program,
   registers: Registers<i64>,
   pc: usize,
   line: usize,
   halt: bool,
+  exit_code: i64,
   error: Option<InterpretError>,
-  reader: Box<dyn BufRead>,
-  writer: Box<dyn Write>,
+  input: Box<dyn Input>,
+  output: Box<dyn Output>,
+  steps: usize,
+  max_steps: Option<usize>,
+  max_register: Option<usize>,
+  max_output: Option<usize>,
+  output_count: usize,
+  timeout_check_interval: usize,
+  register_init_policy: RegisterInitPolicy,
+  div_by_zero_policy: DivByZeroPolicy,
+  overflow_policy: OverflowPolicy,
+  end_of_program_policy: EndOfProgramPolicy,
+  trace: Option<Vec<TraceEntry>>,
+  execution_counts: HashMap<usize, u64>,
+  last_write: Option<(usize, i64)>,
+  last_output: Option<i64>,
+  breakpoints: Vec<BreakAt>,
+  stopped_at: Option<usize>,
+  watches: Vec<(usize, WatchCallback)>,
+  char_io: bool,
 }
 
 impl Ram {
-  /// Creates a new [`Ram`] instance with the given program, input reader, and output writer.
+  /// Creates a new [`Ram`] instance with the given program, input source, and output sink.
+  ///
+  /// Any [`BufRead`](std::io::BufRead) can be used as the input source and any
+  /// [`Write`](std::io::Write) as the output sink, both handling one value per line.
   #[inline]
-  pub fn new(program: Program, reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> Self {
+  pub fn new(program: Program, input: Box<dyn Input>, output: Box<dyn Output>) -> Self {
     Ram {
       program,
-      registers: [0; 100].into(),
+      registers: Registers::default(),
       pc: 0,
       line: 0,
       halt: false,
+      exit_code: 0,
       error: None,
-      reader,
-      writer,
+      input,
+      output,
+      steps: 0,
+      max_steps: None,
+      max_register: None,
+      max_output: None,
+      output_count: 0,
+      timeout_check_interval: DEFAULT_TIMEOUT_CHECK_INTERVAL,
+      register_init_policy: RegisterInitPolicy::default(),
+      div_by_zero_policy: DivByZeroPolicy::default(),
+      overflow_policy: OverflowPolicy::default(),
+      end_of_program_policy: EndOfProgramPolicy::default(),
+      trace: None,
+      execution_counts: HashMap::default(),
+      last_write: None,
+      last_output: None,
+      breakpoints: Vec::new(),
+      stopped_at: None,
+      watches: Vec::new(),
+      char_io: false,
     }
   }
 
+  /// Returns a [`RamBuilder`] for configuring a [`Ram`] instance before creating it.
+  #[inline]
+  pub fn builder() -> RamBuilder {
+    RamBuilder::new()
+  }
+
+  /// Replaces the input source with the whitespace separated values of `input`, e.g.
+  /// `"3 7 -2"`.
+  ///
+  /// Fails with [`InputError::InvalidInput`] if a token is not a number.
+  #[inline]
+  pub fn with_input_str(mut self, input: &str) -> Result<Self, InputError> {
+    self.input = Box::new(input.parse::<VecInput>()?);
+    Ok(self)
+  }
+
+  /// Sets the [`RegisterInitPolicy`] deciding what reading an unset register yields.
+  #[inline]
+  pub fn with_register_init_policy(mut self, policy: RegisterInitPolicy) -> Self {
+    self.register_init_policy = policy;
+    self
+  }
+
+  /// Sets the [`DivByZeroPolicy`] deciding what dividing by zero yields.
+  #[inline]
+  pub fn with_div_by_zero_policy(mut self, policy: DivByZeroPolicy) -> Self {
+    self.div_by_zero_policy = policy;
+    self
+  }
+
+  /// Sets the [`OverflowPolicy`] deciding what an overflowing arithmetic instruction yields.
+  #[inline]
+  pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+    self.overflow_policy = policy;
+    self
+  }
+
+  /// Sets the [`EndOfProgramPolicy`] deciding what happens when execution reaches the end of
+  /// the program without executing `HALT`.
+  #[inline]
+  pub fn with_end_of_program_policy(mut self, policy: EndOfProgramPolicy) -> Self {
+    self.end_of_program_policy = policy;
+    self
+  }
+
+  /// Makes `INPUT`/`READ` read characters and `OUTPUT`/`WRITE` write characters, using their
+  /// code points as values, see [`Input::next_char`] and [`Output::write_char`].
+  ///
+  /// Writing a value that is not a Unicode scalar value fails with
+  /// [`InterpretError::InvalidChar`].
+  pub fn with_char_io(mut self) -> Self {
+    self.char_io = true;
+    self
+  }
+
+  /// Limits the number of instructions the [`Ram`] instance may execute.
+  ///
+  /// Once `limit` instructions have been executed, the next step fails with
+  /// [`InterpretError::StepLimitExceeded`] and the machine halts. This protects against
+  /// programs that loop forever.
+  #[inline]
+  pub fn with_max_steps(mut self, limit: usize) -> Self {
+    self.max_steps = Some(limit);
+    self
+  }
+
+  /// Limits the registers the [`Ram`] instance may access to the indices up to and including
+  /// `max`.
+  ///
+  /// Reading or writing a register above `max`, directly, indirectly or relative to the
+  /// accumulator, fails with [`InterpretError::RegisterIndexOutOfRange`]. This bounds the
+  /// memory used by programs that compute register indices.
+  pub fn with_max_register(mut self, max: usize) -> Self {
+    self.max_register = Some(max);
+    self
+  }
+
+  /// Limits the number of values the [`Ram`] instance may write with `OUTPUT`/`WRITE`.
+  ///
+  /// Writing more than `max` values fails with [`InterpretError::OutputLimitExceeded`]
+  /// without writing the value. This protects against programs flooding the output.
+  pub fn with_max_output(mut self, max: usize) -> Self {
+    self.max_output = Some(max);
+    self
+  }
+
+  /// Sets the number of steps between two checks of the elapsed time in
+  /// [`Ram::run_with_timeout`]. Defaults to [`DEFAULT_TIMEOUT_CHECK_INTERVAL`], an interval of
+  /// `0` is treated as `1`.
+  ///
+  /// Smaller intervals detect a timeout sooner at the cost of reading the clock more often.
+  #[inline]
+  pub fn with_timeout_check_interval(mut self, steps: usize) -> Self {
+    self.timeout_check_interval = steps.max(1);
+    self
+  }
+
+  /// Starts recording every successfully executed instruction as a [`TraceEntry`].
+  ///
+  /// The recorded entries are available through [`Ram::trace`]. Enabling the trace again
+  /// keeps the entries recorded so far.
+  #[inline]
+  pub fn enable_trace(&mut self) {
+    self.trace.get_or_insert_with(Vec::new);
+  }
+
+  /// Returns the instructions recorded since [`Ram::enable_trace`] was called, oldest first.
+  ///
+  /// Returns an empty slice if tracing is not enabled.
+  #[inline]
+  pub fn trace(&self) -> &[TraceEntry] {
+    self.trace.as_deref().unwrap_or_default()
+  }
+
+  /// Returns how many times each instruction was executed, keyed by its index in the
+  /// program. Instructions that never ran are not present.
+  #[inline]
+  pub fn execution_counts(&self) -> HashMap<usize, u64> {
+    self.execution_counts.clone()
+  }
+
+  /// Returns the total number of instructions executed so far.
+  #[inline]
+  pub fn total_steps(&self) -> usize {
+    self.steps
+  }
+
   /// Returns a reference to the registers of the [`Ram`] instance.
   #[inline]
   pub fn get_registers(&self) -> &Registers<i64> {
     &self.registers
   }
 
+  /// Returns the value of the register at the given index, or `None` if it was never set.
+  #[inline]
+  pub fn register(&self, index: usize) -> Option<i64> {
+    self.registers.try_get(index)
+  }
+
+  /// Returns the value of the accumulator, register `0`.
+  #[inline]
+  pub fn accumulator(&self) -> i64 {
+    self.registers.get(0)
+  }
+
+  /// Returns an iterator over the indices and values of the registers that have been set,
+  /// in ascending index order.
+  #[inline]
+  pub fn registers(&self) -> impl Iterator<Item = (usize, i64)> {
+    self.registers.iter()
+  }
+
+  /// Sets the value of the register at the given index, e.g. to seed the input of a program
+  /// before [`Ram::run`].
+  ///
+  /// The register counts as initialized for [`RegisterInitPolicy::Error`].
+  #[inline]
+  pub fn set_register(&mut self, index: usize, value: i64) {
+    self.registers.set(index, value);
+  }
+
+  /// Returns whether the machine has halted, either by executing `HALT`, by reaching the end
+  /// of the program or because an instruction failed.
+  #[inline]
+  pub fn is_halted(&self) -> bool {
+    self.halt
+  }
+
+  /// Returns the exit code given to `HALT`, e.g. `1` after `HALT 1`.
+  ///
+  /// The code is `0` until the program halts and when it halts by a plain `HALT` or at the end
+  /// of the program.
+  #[inline]
+  pub fn exit_code(&self) -> i64 {
+    self.exit_code
+  }
+
+  /// Returns the number of values written with `OUTPUT`/`WRITE` so far.
+  #[inline]
+  pub fn output_count(&self) -> usize {
+    self.output_count
+  }
+
+  /// Returns the program counter, the index of the instruction executed by the next step.
+  #[inline]
+  pub fn pc(&self) -> usize {
+    self.pc
+  }
+
   /// Returns the current instruction of the program as an [`Option<Stmt>`].
   #[inline]
   pub fn get_current_instruction(&self) -> Option<Stmt> {
@@ -110,21 +484,253 @@ impl Ram {
 
   /// Runs the program until it halts or encounters an error.
   pub fn run(&mut self) -> Result<(), InterpretError> {
+    while self.step()? == StepResult::Running {}
+    Ok(())
+  }
+
+  /// Runs the program until it halts, encounters an error or runs longer than `timeout`.
+  ///
+  /// The elapsed time is only checked every [`Ram::with_timeout_check_interval`] steps to keep
+  /// the clock overhead low, so the program may run slightly longer than `timeout`. Once it is
+  /// exceeded, the run fails with [`InterpretError::Timeout`] and the machine halts.
+  pub fn run_with_timeout(&mut self, timeout: Duration) -> Result<(), InterpretError> {
+    let start = Instant::now();
+    let mut steps = 0usize;
+    while self.step()? == StepResult::Running {
+      steps += 1;
+      if steps.is_multiple_of(self.timeout_check_interval) && start.elapsed() >= timeout {
+        self.halt = true;
+        return Err(InterpretError::Timeout(self.line));
+      }
+    }
+    Ok(())
+  }
+
+  /// Runs the program step by step, yielding an [`Event`] for every value written to the
+  /// output sink and a final [`Event::Halted`].
+  ///
+  /// The values are still written to the output sink. If an instruction fails, its error is
+  /// yielded last. Execution only advances while the iterator is consumed.
+  pub fn run_yielding(&mut self) -> impl Iterator<Item = Result<Event, InterpretError>> + '_ {
+    let mut done = self.halt;
+    std::iter::from_fn(move || {
+      while !done {
+        match self.step() {
+          Ok(result) => {
+            done = result == StepResult::Halted;
+            if let Some(value) = self.last_output {
+              return Some(Ok(Event::Output(value)));
+            }
+            if done {
+              return Some(Ok(Event::Halted));
+            }
+          }
+          Err(error) => {
+            done = true;
+            return Some(Err(error));
+          }
+        }
+      }
+      None
+    })
+  }
+
+  /// Adds a breakpoint stopping [`Ram::run_to_breakpoint`]. Adding a breakpoint twice has no
+  /// effect.
+  pub fn add_breakpoint(&mut self, at: BreakAt) {
+    if !self.breakpoints.contains(&at) {
+      self.breakpoints.push(at);
+    }
+  }
+
+  /// Removes a breakpoint added with [`Ram::add_breakpoint`]. Returns whether it existed.
+  pub fn remove_breakpoint(&mut self, at: &BreakAt) -> bool {
+    let len = self.breakpoints.len();
+    self.breakpoints.retain(|breakpoint| breakpoint != at);
+    self.breakpoints.len() != len
+  }
+
+  /// Calls `callback` with the old and the new value whenever the program writes the register
+  /// at `index` with `STORE`, `READ`, `INC`, `DEC` or `SWAP`, including writes through an
+  /// indirect or relative reference resolving to `index`.
+  ///
+  /// The old value is `None` if the register was never written, whatever the
+  /// [`RegisterInitPolicy`] would yield when reading it. The callback is also called when the
+  /// written value equals the old one.
+  pub fn watch_register(&mut self, index: usize, callback: impl FnMut(Option<i64>, i64) + 'static) {
+    self.watches.push((index, Box::new(callback)));
+  }
+
+  /// Runs the program until an instruction with a breakpoint is about to be executed or the
+  /// machine halts.
+  ///
+  /// A breakpoint on the instruction at the current program counter stops before executing
+  /// anything, unless the machine stopped there the last time, so calling this again after
+  /// stopping at a breakpoint continues past it. Breakpoints on labels that are not defined
+  /// are never hit.
+  pub fn run_to_breakpoint(&mut self) -> Result<StopReason, InterpretError> {
+    loop {
+      let resuming = self.stopped_at.take() == Some(self.pc);
+      if !resuming && !self.halt && self.is_breakpoint(self.pc) {
+        self.stopped_at = Some(self.pc);
+        return Ok(StopReason::Breakpoint(self.pc));
+      }
+      if self.step()? == StepResult::Halted {
+        return Ok(StopReason::Halted);
+      }
+    }
+  }
+
+  /// Runs a [`Compiled`] version of the program until it halts or encounters an error.
+  ///
+  /// `compiled` must be compiled from the program of this [`Ram`] instance, execution
+  /// continues from the current program counter. Registers, the step limit and the configured
+  /// policies behave as with [`Ram::run`], but neither the trace nor the execution counts are
+  /// recorded.
+  pub fn run_compiled(&mut self, compiled: &Compiled) -> Result<(), InterpretError> {
+    if self.halt {
+      return Err(InterpretError::Halted(self.line));
+    }
+
     while !self.halt {
-      self.step()?;
+      if self
+        .max_steps
+        .is_some_and(|max_steps| self.steps >= max_steps)
+      {
+        self.halt = true;
+        return Err(InterpretError::StepLimitExceeded(self.line));
+      }
+
+      if self.halts_at_end() {
+        self.halt = true;
+        break;
+      }
+      let Some((op, line)) = compiled.get(self.pc) else {
+        self.halt = true;
+        return Err(self.out_of_program());
+      };
+      self.line = line;
+
+      match self.execute(op) {
+        Ok(next_pc) => {
+          self.pc = next_pc;
+          self.steps += 1;
+        }
+        Err(error) => {
+          self.halt = true;
+          return Err(error);
+        }
+      }
     }
     Ok(())
   }
 
-  /// Executes one step of the program and advances the program counter.
-  pub fn step(&mut self) -> Result<(), InterpretError> {
+  /// Executes exactly one instruction of the program and advances the program counter.
+  ///
+  /// Returns whether the machine halted or is still running, so callers can drive the
+  /// execution step by step, e.g. to build a debugger.
+  pub fn step(&mut self) -> Result<StepResult, InterpretError> {
+    self.last_write = None;
+    self.last_output = None;
+    self.stopped_at = None;
+
+    if !self.halt
+      && self
+        .max_steps
+        .is_some_and(|max_steps| self.steps >= max_steps)
+    {
+      self.halt = true;
+      return Err(InterpretError::StepLimitExceeded(self.line));
+    }
+
+    if !self.halt && self.halts_at_end() {
+      self.halt = true;
+      return Ok(StepResult::Halted);
+    }
+
+    let index = self.pc;
+    let accumulator_before = self.accumulator();
+
     let result = self.eval_current();
     if let Ok(next_pc) = result {
+      let accumulator_after = self.accumulator();
+      if let (Some(trace), Some(stmt)) = (self.trace.as_mut(), self.program.get(index)) {
+        trace.push(TraceEntry {
+          index,
+          stmt: stmt.clone(),
+          accumulator_before,
+          accumulator_after,
+          register: self.last_write,
+        });
+      }
+      *self.execution_counts.entry(index).or_default() += 1;
       self.pc = next_pc;
+      self.steps += 1;
     } else {
       self.halt = true;
     }
-    result.map(|_| ())
+    result.map(|_| {
+      if self.halt {
+        StepResult::Halted
+      } else {
+        StepResult::Running
+      }
+    })
+  }
+
+  /// Captures the current state of the [`Ram`] instance as a [`RamState`].
+  ///
+  /// Snapshots can be kept in a history and later passed to [`Ram::restore`].
+  #[inline]
+  pub fn snapshot(&self) -> RamState {
+    RamState::from(self)
+  }
+
+  /// Restores the state captured by [`Ram::snapshot`].
+  ///
+  /// Only the state stored in [`RamState`] is restored: values already consumed from the
+  /// input source and values already written to the output sink are not rewound.
+  #[inline]
+  pub fn restore(&mut self, state: RamState) {
+    self.program = state.program;
+    self.registers = state.registers;
+    self.pc = state.pc;
+    self.line = state.line;
+    self.halt = state.halt;
+    self.exit_code = state.exit_code;
+    self.error = state.error;
+    self.steps = state.steps;
+    self.stopped_at = None;
+  }
+
+  /// Resets the machine to its initial state while keeping the loaded program.
+  ///
+  /// Registers, the program counter, the halt and error state, the step and execution
+  /// counters and the recorded trace are cleared. The input source and output sink are kept
+  /// as they are; use [`Ram::reset_with_input`] to run the program against new input.
+  pub fn reset(&mut self) {
+    self.registers = Registers::default();
+    self.pc = 0;
+    self.line = 0;
+    self.halt = false;
+    self.exit_code = 0;
+    self.error = None;
+    self.steps = 0;
+    self.output_count = 0;
+    self.stopped_at = None;
+    self.execution_counts.clear();
+    self.last_write = None;
+    self.last_output = None;
+    if let Some(trace) = self.trace.as_mut() {
+      trace.clear();
+    }
+  }
+
+  /// Resets the machine like [`Ram::reset`] and replaces its input source.
+  #[inline]
+  pub fn reset_with_input(&mut self, input: Box<dyn Input>) {
+    self.reset();
+    self.input = input;
   }
 
   /// Returns the current error state of the [`Ram`] instance as an
@@ -144,89 +750,283 @@ impl Ram {
     Ok(())
   }
 
+  fn is_breakpoint(&self, index: usize) -> bool {
+    self.breakpoints.iter().any(|breakpoint| match breakpoint {
+      BreakAt::Label(label) => self.program.labels.get(label) == Some(&index),
+      BreakAt::Index(at) => *at == index,
+    })
+  }
+
   fn eval_current(&mut self) -> Result<usize, InterpretError> {
     if self.halt {
       return Err(InterpretError::Halted(self.line));
     }
 
     let Some(stmt) = self.program.get(self.pc) else {
-      return Err(InterpretError::SegmentationFault(self.line));
+      return Err(self.out_of_program());
     };
 
     self.line = stmt.get_line();
+    let decode = |label| {
+      self
+        .program
+        .decode_label(label)
+        .ok_or(InterpretError::UnknownLabel(self.line))
+    };
+
+    // Labels are only resolved when the jump is taken, so a conditional jump to an unknown
+    // label fails only if its condition holds.
+    let op = match stmt {
+      Stmt::Jump(label, _) => Op::Jump(decode(label)?),
+      Stmt::JumpIfZero(label, _) if self.first()? == 0 => Op::Jump(decode(label)?),
+      Stmt::JumpGreaterZero(label, _) if self.first()? > 0 => Op::Jump(decode(label)?),
+      Stmt::JumpLessZero(label, _) if self.first()? < 0 => Op::Jump(decode(label)?),
+      Stmt::JumpNotZero(label, _) if self.first()? != 0 => Op::Jump(decode(label)?),
+      Stmt::JumpIfZero(..)
+      | Stmt::JumpGreaterZero(..)
+      | Stmt::JumpLessZero(..)
+      | Stmt::JumpNotZero(..) => Op::Nop,
+      stmt => Op::from_stmt(stmt).expect("jumps to labels are handled above"),
+    };
+    self.execute(&op)
+  }
+
+  /// Returns whether the program counter is right after the last instruction and the
+  /// [`EndOfProgramPolicy`] treats that as halting.
+  #[inline]
+  fn halts_at_end(&self) -> bool {
+    self.end_of_program_policy == EndOfProgramPolicy::Halt
+      && self.pc == self.program.instructions.len()
+  }
+
+  /// Returns the error for a program counter outside the program.
+  #[inline]
+  fn out_of_program(&self) -> InterpretError {
+    if self.pc == self.program.instructions.len() {
+      InterpretError::FellOffEnd(self.line)
+    } else {
+      InterpretError::SegmentationFault(self.line)
+    }
+  }
+
+  /// Executes a single [`Op`] on behalf of the instruction at the program counter and returns
+  /// the index of the next instruction.
+  fn execute(&mut self, op: &Op) -> Result<usize, InterpretError> {
     let mut next_pc = self.pc + 1;
 
-    match stmt {
-      Stmt::Label(..) => {}
-      Stmt::Load(value, _) => self.set_first(self.get_with_value(value)?),
-      Stmt::Store(value, _) => {
-        let index: usize = self
-          .get_with_register(value)?
+    match op {
+      Op::Nop => {}
+      Op::Load(value) => self.set_first(self.get_with_value(value)?),
+      Op::Store(value) => {
+        let index = self.register_index(value)?;
+        self.write(index, self.first()?)?;
+      }
+      Op::Add(value) => {
+        let value = self.get_with_value(value)?;
+        let result = self.overflowing(
+          self.first()?,
+          value,
+          i64::checked_add,
+          i64::wrapping_add,
+          i64::saturating_add,
+        )?;
+        self.set_first(result);
+      }
+      Op::Sub(value) => {
+        let value = self.get_with_value(value)?;
+        let result = self.overflowing(
+          self.first()?,
+          value,
+          i64::checked_sub,
+          i64::wrapping_sub,
+          i64::saturating_sub,
+        )?;
+        self.set_first(result);
+      }
+      Op::Mul(value) => {
+        let value = self.get_with_value(value)?;
+        let result = self.overflowing(
+          self.first()?,
+          value,
+          i64::checked_mul,
+          i64::wrapping_mul,
+          i64::saturating_mul,
+        )?;
+        self.set_first(result);
+      }
+      Op::Div(value) => {
+        let divisor = self.get_with_value(value)?;
+        let result = self.divide(
+          divisor,
+          i64::checked_div,
+          i64::wrapping_div,
+          i64::saturating_div,
+        )?;
+        self.set_first(result);
+      }
+      Op::Mod(value) => {
+        let divisor = self.get_with_value(value)?;
+        // The only overflowing remainder, `i64::MIN % -1`, is `0`.
+        let result = self.divide(
+          divisor,
+          i64::checked_rem,
+          i64::wrapping_rem,
+          i64::wrapping_rem,
+        )?;
+        self.set_first(result);
+      }
+      Op::Pow(value) => {
+        let exponent: u32 = self
+          .get_with_value(value)?
           .try_into()
-          .map_err(|_| InterpretError::SegmentationFault(self.line))?;
-        self.registers.set(index, self.first());
+          .map_err(|_| InterpretError::InvalidLiteral(self.line))?;
+        let result = self.overflowing(
+          self.first()?,
+          exponent,
+          i64::checked_pow,
+          i64::wrapping_pow,
+          i64::saturating_pow,
+        )?;
+        self.set_first(result);
       }
-      Stmt::Add(value, _) => self.set_first(self.first() + self.get_with_value(value)?),
-      Stmt::Sub(value, _) => self.set_first(self.first() - self.get_with_value(value)?),
-      Stmt::Mul(value, _) => self.set_first(self.first() * self.get_with_value(value)?),
-      Stmt::Div(value, _) => {
-        self.set_first(
-          self
-            .first()
-            .checked_div(self.get_with_value(value)?)
-            .ok_or(InterpretError::DivisionByZero(self.line))?,
-        );
+      Op::Inc(value) => {
+        let index = self.register_index(value)?;
+        let result = self.overflowing(
+          self.read(index)?,
+          1,
+          i64::checked_add,
+          i64::wrapping_add,
+          i64::saturating_add,
+        )?;
+        self.write(index, result)?;
       }
-      Stmt::Jump(label, _) => {
-        next_pc = self
-          .program
-          .decode_label(label)
-          .ok_or(InterpretError::UnknownLabel(self.line))?;
+      Op::Dec(value) => {
+        let index = self.register_index(value)?;
+        let result = self.overflowing(
+          self.read(index)?,
+          1,
+          i64::checked_sub,
+          i64::wrapping_sub,
+          i64::saturating_sub,
+        )?;
+        self.write(index, result)?;
       }
-      Stmt::JumpIfZero(label, _) => {
-        if self.first() == 0 {
-          next_pc = self
-            .program
-            .decode_label(label)
-            .ok_or(InterpretError::UnknownLabel(self.line))?;
+      Op::Swap(value) => {
+        let index = self.register_index(value)?;
+        let (accumulator, register) = (self.first()?, self.read(index)?);
+        if index != 0 {
+          self.write(0, register)?;
         }
+        self.write(index, accumulator)?;
       }
-      Stmt::JumpGreatherZero(label, _) => {
-        if self.first() > 0 {
-          next_pc = self
-            .program
-            .decode_label(label)
-            .ok_or(InterpretError::UnknownLabel(self.line))?;
+      Op::Jump(target) => next_pc = *target,
+      Op::JumpIfZero(target) => {
+        if self.first()? == 0 {
+          next_pc = *target;
         }
       }
-      Stmt::Output(value, _) => {
-        let value = self.get_with_value(value)?;
-        writeln!(&mut self.writer, "{}", value).map_err(|_| InterpretError::IOError(self.line))?
+      Op::JumpGreaterZero(target) => {
+        if self.first()? > 0 {
+          next_pc = *target;
+        }
       }
-      Stmt::Input(value, _) => {
-        let mut input = String::new();
-        self
-          .reader
-          .read_line(&mut input)
-          .map_err(|_| InterpretError::IOError(self.line))?;
-        let index: usize = self
-          .get_with_register(value)?
+      Op::JumpLessZero(target) => {
+        if self.first()? < 0 {
+          next_pc = *target;
+        }
+      }
+      Op::JumpNotZero(target) => {
+        if self.first()? != 0 {
+          next_pc = *target;
+        }
+      }
+      Op::JumpIndirect(value) => {
+        next_pc = self
+          .get_with_value(&Value::Register(*value))?
           .try_into()
-          .map_err(|_| InterpretError::SegmentationFault(self.line))?;
-        self.registers.set(
-          index,
-          input
-            .trim()
-            .parse()
-            .map_err(|_| InterpretError::InvalidInput(self.line, input))?,
-        );
+          .ok()
+          .filter(|target| *target < self.program.instructions.len())
+          .ok_or(InterpretError::InvalidJumpTarget(self.line))?;
+      }
+      Op::Output(value) => {
+        let value = self.get_with_value(value)?;
+        if self
+          .max_output
+          .is_some_and(|max_output| self.output_count >= max_output)
+        {
+          Err(InterpretError::OutputLimitExceeded(self.line))?
+        }
+        match self.char_io {
+          true => self.output.write_char(value),
+          false => self.output.write_output(value),
+        }
+        .map_err(|error| error.at(self.line))?;
+        self.output_count += 1;
+        self.last_output = Some(value);
+      }
+      Op::Input(value) => {
+        let input = match self.char_io {
+          true => self.input.next_char(),
+          false => self.input.next_input(),
+        }
+        .map_err(|error| error.at(self.line))?
+        .ok_or(InterpretError::NoInput(self.line))?;
+        let index = self.register_index(value)?;
+        self.write(index, input)?;
+      }
+      Op::Halt => self.halt = true,
+      Op::HaltCode(code) => {
+        self.halt = true;
+        self.exit_code = *code;
       }
-      Stmt::Halt(_) => self.halt = true,
     };
 
     Ok(next_pc)
   }
 
+  /// Divides the accumulator by `divisor`, handling a zero divisor according to the
+  /// [`DivByZeroPolicy`] and an overflow according to the [`OverflowPolicy`].
+  #[inline]
+  fn divide(
+    &self,
+    divisor: i64,
+    checked: fn(i64, i64) -> Option<i64>,
+    wrapping: fn(i64, i64) -> i64,
+    saturating: fn(i64, i64) -> i64,
+  ) -> Result<i64, InterpretError> {
+    let dividend = self.first()?;
+    if divisor == 0 {
+      return match self.div_by_zero_policy {
+        DivByZeroPolicy::Error => Err(InterpretError::DivisionByZero(self.line)),
+        DivByZeroPolicy::Saturate => Ok(match dividend.signum() {
+          1 => i64::MAX,
+          -1 => i64::MIN,
+          _ => 0,
+        }),
+        DivByZeroPolicy::Zero => Ok(0),
+      };
+    }
+    self.overflowing(dividend, divisor, checked, wrapping, saturating)
+  }
+
+  /// Applies an arithmetic operation, picking its variant according to the
+  /// [`OverflowPolicy`].
+  #[inline]
+  fn overflowing<T>(
+    &self,
+    lhs: i64,
+    rhs: T,
+    checked: fn(i64, T) -> Option<i64>,
+    wrapping: fn(i64, T) -> i64,
+    saturating: fn(i64, T) -> i64,
+  ) -> Result<i64, InterpretError> {
+    match self.overflow_policy {
+      OverflowPolicy::Wrap => Ok(wrapping(lhs, rhs)),
+      OverflowPolicy::Saturate => Ok(saturating(lhs, rhs)),
+      OverflowPolicy::Error => checked(lhs, rhs).ok_or(InterpretError::Overflow(self.line)),
+    }
+  }
+
   #[inline]
   fn get_with_value(&self, value: &Value) -> Result<i64, InterpretError> {
     match value {
@@ -235,6 +1035,15 @@ impl Ram {
         .map_err(|_| InterpretError::SegmentationFault(self.line)),
       Value::Register(RegisterValue::Direct(index)) => self.get::<1>(*index),
       Value::Register(RegisterValue::Indirect(index)) => self.get::<2>(*index),
+      Value::Register(RegisterValue::Relative(offset)) => {
+        let index: usize = self
+          .relative_index(*offset)?
+          .try_into()
+          .map_err(|_| InterpretError::SegmentationFault(self.line))?;
+        self.read(index)
+      }
+      // Addresses of defined labels are resolved when the program is built.
+      Value::LabelAddr(_) => Err(InterpretError::UnknownLabel(self.line)),
     }
   }
 
@@ -243,17 +1052,78 @@ impl Ram {
     match value {
       RegisterValue::Direct(index) => self.get::<0>(*index),
       RegisterValue::Indirect(index) => self.get::<1>(*index),
+      RegisterValue::Relative(offset) => self.relative_index(*offset),
     }
   }
 
+  /// Returns the index of the register written by `STORE`, `READ`, `INC`, `DEC` or `SWAP`.
+  #[inline]
+  fn register_index(&self, value: &RegisterValue) -> Result<usize, InterpretError> {
+    self
+      .get_with_register(value)?
+      .try_into()
+      .map_err(|_| match value {
+        RegisterValue::Indirect(_) => InterpretError::NegativeIndirectIndex(self.line),
+        _ => InterpretError::SegmentationFault(self.line),
+      })
+  }
+
+  #[inline]
+  fn relative_index(&self, offset: usize) -> Result<i64, InterpretError> {
+    let offset: i64 = offset
+      .try_into()
+      .map_err(|_| InterpretError::SegmentationFault(self.line))?;
+    self
+      .first()?
+      .checked_add(offset)
+      .ok_or(InterpretError::SegmentationFault(self.line))
+  }
+
   #[inline]
   fn set_first(&mut self, value: i64) {
     self.registers.set(0, value);
   }
 
   #[inline]
-  fn first(&self) -> i64 {
-    self.registers.get(0)
+  fn write(&mut self, index: usize, value: i64) -> Result<(), InterpretError> {
+    self.check_register(index)?;
+    let old = self.registers.try_get(index);
+    self.registers.set(index, value);
+    self.last_write = Some((index, value));
+    for (_, callback) in self
+      .watches
+      .iter_mut()
+      .filter(|(watched, _)| *watched == index)
+    {
+      callback(old, value);
+    }
+    Ok(())
+  }
+
+  #[inline]
+  fn first(&self) -> Result<i64, InterpretError> {
+    self.read(0)
+  }
+
+  #[inline]
+  fn read(&self, index: usize) -> Result<i64, InterpretError> {
+    self.check_register(index)?;
+    match self.register_init_policy {
+      RegisterInitPolicy::Zero => Ok(self.registers.get(index)),
+      RegisterInitPolicy::Error => self
+        .registers
+        .try_get(index)
+        .ok_or(InterpretError::UninitializedRegister(self.line, index)),
+    }
+  }
+
+  /// Fails if `index` is above the limit set with [`Ram::with_max_register`].
+  #[inline]
+  fn check_register(&self, index: usize) -> Result<(), InterpretError> {
+    match self.max_register {
+      Some(max) if index > max => Err(InterpretError::RegisterIndexOutOfRange(self.line, index)),
+      _ => Ok(()),
+    }
   }
 
   fn get<const N: usize>(&self, index: usize) -> Result<i64, InterpretError> {
@@ -266,12 +1136,11 @@ impl Ram {
     let mut index = index;
     for _ in 0..N - 1 {
       index = self
-        .registers
-        .get(index)
+        .read(index)?
         .try_into()
-        .map_err(|_| InterpretError::SegmentationFault(self.line))?
+        .map_err(|_| InterpretError::NegativeIndirectIndex(self.line))?
     }
-    Ok(self.registers.get(index))
+    self.read(index)
   }
 }
 
@@ -298,15 +1167,154 @@ impl Iterator for Ram {
   }
 }
 
+/// Builds a configured [`Ram`] instance, see [`Ram::builder`].
+///
+/// Every option defaults to the behavior of [`Ram::new`]: no input, output discarded, no step
+/// or register limit, the default policies and numeric I/O.
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::io::VecInput;
+/// use ramemu::program::Program;
+/// use ramemu::ram::{OverflowPolicy, Ram};
+///
+/// let program = Program::from_source("READ 0\nMUL =2\nHALT").unwrap();
+/// let mut ram = Ram::builder()
+///   .with_input(Box::new(VecInput::from(vec![21])))
+///   .with_overflow_policy(OverflowPolicy::Wrap)
+///   .with_max_steps(100)
+///   .build(program);
+///
+/// ram.run().unwrap();
+/// assert_eq!(ram.accumulator(), 42);
+/// ```
+#[derive(Default)]
+pub struct RamBuilder {
+  input: Option<Box<dyn Input>>,
+  output: Option<Box<dyn Output>>,
+  max_steps: Option<usize>,
+  max_register: Option<usize>,
+  max_output: Option<usize>,
+  timeout_check_interval: Option<usize>,
+  register_init_policy: RegisterInitPolicy,
+  div_by_zero_policy: DivByZeroPolicy,
+  overflow_policy: OverflowPolicy,
+  end_of_program_policy: EndOfProgramPolicy,
+  char_io: bool,
+}
+
+impl RamBuilder {
+  /// Creates a builder with every option at its default.
+  #[inline]
+  pub fn new() -> Self {
+    RamBuilder::default()
+  }
+
+  /// Sets the input source. Defaults to an empty input.
+  pub fn with_input(mut self, input: Box<dyn Input>) -> Self {
+    self.input = Some(input);
+    self
+  }
+
+  /// Sets the output sink. Defaults to discarding the output.
+  pub fn with_output(mut self, output: Box<dyn Output>) -> Self {
+    self.output = Some(output);
+    self
+  }
+
+  /// See [`Ram::with_max_steps`].
+  pub fn with_max_steps(mut self, limit: usize) -> Self {
+    self.max_steps = Some(limit);
+    self
+  }
+
+  /// See [`Ram::with_max_register`].
+  pub fn with_max_register(mut self, max: usize) -> Self {
+    self.max_register = Some(max);
+    self
+  }
+
+  /// See [`Ram::with_max_output`].
+  pub fn with_max_output(mut self, max: usize) -> Self {
+    self.max_output = Some(max);
+    self
+  }
+
+  /// See [`Ram::with_timeout_check_interval`].
+  pub fn with_timeout_check_interval(mut self, steps: usize) -> Self {
+    self.timeout_check_interval = Some(steps);
+    self
+  }
+
+  /// See [`Ram::with_register_init_policy`].
+  pub fn with_register_init_policy(mut self, policy: RegisterInitPolicy) -> Self {
+    self.register_init_policy = policy;
+    self
+  }
+
+  /// See [`Ram::with_div_by_zero_policy`].
+  pub fn with_div_by_zero_policy(mut self, policy: DivByZeroPolicy) -> Self {
+    self.div_by_zero_policy = policy;
+    self
+  }
+
+  /// See [`Ram::with_overflow_policy`].
+  pub fn with_overflow_policy(mut self, policy: OverflowPolicy) -> Self {
+    self.overflow_policy = policy;
+    self
+  }
+
+  /// See [`Ram::with_end_of_program_policy`].
+  pub fn with_end_of_program_policy(mut self, policy: EndOfProgramPolicy) -> Self {
+    self.end_of_program_policy = policy;
+    self
+  }
+
+  /// See [`Ram::with_char_io`].
+  pub fn with_char_io(mut self) -> Self {
+    self.char_io = true;
+    self
+  }
+
+  /// Creates the [`Ram`] instance running `program`.
+  pub fn build(self, program: Program) -> Ram {
+    let input = self.input.unwrap_or_else(|| Box::new(std::io::empty()));
+    let output = self.output.unwrap_or_else(|| Box::new(std::io::sink()));
+    let mut ram = Ram::new(program, input, output)
+      .with_register_init_policy(self.register_init_policy)
+      .with_div_by_zero_policy(self.div_by_zero_policy)
+      .with_overflow_policy(self.overflow_policy)
+      .with_end_of_program_policy(self.end_of_program_policy);
+    ram.max_steps = self.max_steps;
+    ram.max_register = self.max_register;
+    ram.max_output = self.max_output;
+    if let Some(steps) = self.timeout_check_interval {
+      ram = ram.with_timeout_check_interval(steps);
+    }
+    ram.char_io = self.char_io;
+    ram
+  }
+}
+
 /// The [`RamState`] struct represents a snapshot of a RAM machine's state.
+///
+/// It captures the program, registers, program counter, line, halt state, exit code, error
+/// state, and the number of executed steps. The input source and output sink are not part of the state.
+///
+/// With the `serde` feature enabled, states can be serialized, e.g. to store the final state
+/// of a reference solution and compare it with [`RamState::diff`] later.
 #[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RamState {
   pub program: Program,
   pub registers: Registers<i64>,
   pub pc: usize,
   pub line: usize,
   pub halt: bool,
+  pub exit_code: i64,
   pub error: Option<InterpretError>,
+  pub steps: usize,
 }
 
 impl From<Ram> for RamState {
@@ -318,7 +1326,9 @@ impl From<Ram> for RamState {
       pc: ram.pc,
       line: ram.line,
       halt: ram.halt,
+      exit_code: ram.exit_code,
       error: ram.error,
+      steps: ram.steps,
     }
   }
 }
@@ -332,23 +1342,998 @@ impl From<&Ram> for RamState {
       pc: ram.pc,
       line: ram.line,
       halt: ram.halt,
+      exit_code: ram.exit_code,
       error: ram.error.clone(),
+      steps: ram.steps,
     }
   }
 }
 
 impl RamState {
-  /// Creates a new [`Ram`] instance from the given [`RamState`], input reader, and output writer.
-  pub fn create_ram(self, reader: Box<dyn BufRead>, writer: Box<dyn Write>) -> Ram {
-    Ram {
-      program: self.program,
-      registers: self.registers,
-      pc: self.pc,
-      line: self.line,
-      halt: self.halt,
-      error: self.error,
-      reader,
-      writer,
+  /// Creates a new [`Ram`] instance from the given [`RamState`], input source, and output sink.
+  pub fn create_ram(self, input: Box<dyn Input>, output: Box<dyn Output>) -> Ram {
+    let mut ram = Ram::new(Program::default(), input, output);
+    ram.restore(self);
+    ram
+  }
+
+  /// Returns the differences between the memory of this state and `other`.
+  ///
+  /// Only the accumulator and the registers are compared, the rest of the state is ignored.
+  pub fn diff(&self, other: &RamState) -> StateDiff {
+    let (accumulator, other_accumulator) = (self.registers.get(0), other.registers.get(0));
+    let indices: BTreeSet<usize> = self
+      .registers
+      .iter()
+      .chain(other.registers.iter())
+      .map(|(index, _)| index)
+      .filter(|index| *index != 0)
+      .collect();
+
+    StateDiff {
+      accumulator: (accumulator != other_accumulator).then_some((accumulator, other_accumulator)),
+      registers: indices
+        .into_iter()
+        .map(|index| {
+          let values = (
+            self.registers.try_get(index),
+            other.registers.try_get(index),
+          );
+          (index, values.0, values.1)
+        })
+        .filter(|(_, value, other_value)| value != other_value)
+        .collect(),
+    }
+  }
+}
+
+/// The differences between the memory of two [`RamState`]s, as returned by [`RamState::diff`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StateDiff {
+  /// The accumulator of both states, if it differs.
+  pub accumulator: Option<(i64, i64)>,
+  /// Every other register whose value differs, as its index and its value in both states, in
+  /// ascending index order. The value of a register that was never written is `None`.
+  pub registers: Vec<(usize, Option<i64>, Option<i64>)>,
+}
+
+impl StateDiff {
+  /// Returns `true` if both states hold the same values.
+  #[inline]
+  pub fn is_empty(&self) -> bool {
+    self.accumulator.is_none() && self.registers.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use crate::errors::ParseError;
+  use crate::errors::Span;
+  use crate::io::CharOutput;
+  use crate::io::VecInput;
+  use crate::io::VecOutput;
+  use std::cell::RefCell;
+  use std::rc::Rc;
+
+  fn ram_from(source: &str) -> Ram {
+    let program = Program::from_source(source).unwrap();
+    Ram::new(
+      program,
+      Box::new(std::io::empty()),
+      Box::new(std::io::sink()),
+    )
+  }
+
+  fn ram_with_input(source: &str, input: Vec<i64>) -> Ram {
+    let program = Program::from_source(source).unwrap();
+    Ram::new(
+      program,
+      Box::new(VecInput::from(input)),
+      Box::new(std::io::sink()),
+    )
+  }
+
+  #[test]
+  fn test_mod() {
+    let mut ram = ram_from("LOAD =17\nMOD =5\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), 2);
+  }
+
+  #[test]
+  fn test_pow() {
+    let mut ram = ram_from("LOAD =3\nPOW =4\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), 81);
+  }
+
+  #[test]
+  fn test_mod_by_zero() {
+    let mut ram = ram_from("LOAD =17\nMOD =0\nHALT");
+
+    assert_eq!(ram.run(), Err(InterpretError::DivisionByZero(2)));
+  }
+
+  #[test]
+  fn test_negative_values() {
+    let mut ram = ram_from("LOAD =-5\nADD =-10\nSTORE 1\nSUB =-20\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), 5);
+    assert_eq!(ram.get_registers().get(1), -15);
+  }
+
+  #[test]
+  fn test_step_reports_halt() {
+    let mut ram = ram_from("LOAD =1\nADD =2\nHALT");
+
+    assert_eq!(ram.step(), Ok(StepResult::Running));
+    assert_eq!(ram.get_registers().get(0), 1);
+    assert_eq!(ram.step(), Ok(StepResult::Running));
+    assert_eq!(ram.get_registers().get(0), 3);
+    assert_eq!(ram.step(), Ok(StepResult::Halted));
+    assert_eq!(ram.step(), Err(InterpretError::Halted(3)));
+  }
+
+  #[test]
+  fn test_vec_input() {
+    let mut ram = ram_with_input("READ 1\nREAD 2\nLOAD 1\nADD 2\nHALT", vec![3, -7]);
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), -4);
+  }
+
+  #[test]
+  fn test_no_input() {
+    let mut ram = ram_with_input("READ 1\nREAD 2\nHALT", vec![3]);
+
+    assert_eq!(ram.run(), Err(InterpretError::NoInput(2)));
+  }
+
+  #[test]
+  fn test_reader_input() {
+    let program = Program::from_source("READ 1\nREAD 2\nHALT").unwrap();
+    let reader = std::io::Cursor::new("12\nabc\n");
+    let mut ram = Ram::new(program, Box::new(reader), Box::new(std::io::sink()));
+
+    assert_eq!(
+      ram.run(),
+      Err(InterpretError::InvalidInput(2, "abc\n".to_string()))
+    );
+    assert_eq!(ram.get_registers().get(1), 12);
+  }
+
+  #[test]
+  fn test_vec_output() {
+    let program =
+      Program::from_source("LOAD =3\nWRITE 0\nWRITE =-1\nMUL =2\nOUTPUT 0\nHALT").unwrap();
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      program,
+      Box::new(std::io::empty()),
+      Box::new(output.clone()),
+    );
+    ram.run().unwrap();
+
+    assert_eq!(output.values(), vec![3, -1, 6]);
+  }
+
+  #[test]
+  fn test_step_limit() {
+    let mut ram = ram_from("a: JUMP a").with_max_steps(10);
+
+    for _ in 0..10 {
+      assert_eq!(ram.step(), Ok(StepResult::Running));
     }
+    assert_eq!(ram.step(), Err(InterpretError::StepLimitExceeded(1)));
+    assert_eq!(ram.step(), Err(InterpretError::Halted(1)));
+  }
+
+  #[test]
+  fn test_step_limit_allows_halting_programs() {
+    let mut ram = ram_from("LOAD =1\nHALT").with_max_steps(2);
+
+    assert_eq!(ram.run(), Ok(()));
+  }
+
+  #[test]
+  fn test_snapshot_and_restore() {
+    let mut ram = ram_from("LOAD =1\nSTORE 1\nADD =1\nSTORE 2\nADD =1\nSTORE 1\nHALT");
+    ram.step().unwrap();
+    ram.step().unwrap();
+    let snapshot = ram.snapshot();
+
+    ram.run().unwrap();
+    assert_eq!(ram.get_registers().get(1), 3);
+    assert_eq!(ram.get_registers().get(2), 2);
+
+    ram.restore(snapshot.clone());
+    assert_eq!(ram.get_registers(), &snapshot.registers);
+    assert_eq!(ram.get_registers().get(0), 1);
+    assert_eq!(ram.get_registers().get(1), 1);
+    assert_eq!(ram.get_registers().get(2), 0);
+    assert_eq!(
+      ram.get_current_instruction(),
+      Some(Stmt::Add(Value::Pure(1), 3))
+    );
+
+    ram.run().unwrap();
+    assert_eq!(ram.get_registers().get(1), 3);
+  }
+
+  #[test]
+  fn test_jump_indirect() {
+    let mut ram = ram_from("LOAD =4\nSTORE 1\nJIND 1\nLOAD =100\nADD =1\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), 5);
+  }
+
+  #[test]
+  fn test_jump_indirect_through_register() {
+    let mut ram = ram_from("LOAD =6\nSTORE 2\nLOAD =2\nSTORE 1\nJIND *1\nHALT\nLOAD =7\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), 7);
+  }
+
+  #[test]
+  fn test_jump_indirect_out_of_range() {
+    let mut ram = ram_from("LOAD =10\nSTORE 1\nJIND 1\nHALT");
+    assert_eq!(ram.run(), Err(InterpretError::InvalidJumpTarget(3)));
+
+    let mut ram = ram_from("LOAD =-1\nSTORE 1\nJIND 1\nHALT");
+    assert_eq!(ram.run(), Err(InterpretError::InvalidJumpTarget(3)));
+  }
+
+  #[test]
+  fn test_run_program_from_statements() {
+    use crate::stmt::Label;
+
+    let program = Program::try_from(vec![
+      Stmt::Load(Value::Pure(3), 1),
+      Stmt::Label("loop".to_string(), 2),
+      Stmt::Sub(Value::Pure(1), 3),
+      Stmt::JumpGreaterZero(Label::new("loop".to_string()), 4),
+      Stmt::Output(Value::Register(RegisterValue::Direct(0)), 5),
+      Stmt::Halt(6),
+    ])
+    .unwrap();
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      program,
+      Box::new(std::io::empty()),
+      Box::new(output.clone()),
+    );
+    ram.run().unwrap();
+
+    assert_eq!(output.values(), vec![0]);
+  }
+
+  #[test]
+  fn test_uninitialized_register_reads_zero() {
+    let mut ram = ram_from("LOAD =1\nADD 5\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.get_registers().get(0), 1);
+  }
+
+  #[test]
+  fn test_uninitialized_register_is_an_error() {
+    let mut ram =
+      ram_from("LOAD =1\nADD 5\nHALT").with_register_init_policy(RegisterInitPolicy::Error);
+    assert_eq!(ram.run(), Err(InterpretError::UninitializedRegister(2, 5)));
+
+    let mut ram = ram_from("LOAD =1\nSTORE 5\nADD *5\nHALT")
+      .with_register_init_policy(RegisterInitPolicy::Error);
+    assert_eq!(ram.run(), Err(InterpretError::UninitializedRegister(3, 1)));
+
+    let mut ram = ram_from("ADD =1\nHALT").with_register_init_policy(RegisterInitPolicy::Error);
+    assert_eq!(ram.run(), Err(InterpretError::UninitializedRegister(1, 0)));
+  }
+
+  #[test]
+  fn test_register_accessors() {
+    let mut ram = ram_from("LOAD =4\nSTORE 1\nADD 7\nMUL =2\nSTORE 2\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.accumulator(), 8);
+    assert_eq!(ram.register(1), Some(4));
+    assert_eq!(ram.register(2), Some(8));
+    assert_eq!(ram.register(7), None);
+    assert_eq!(
+      ram.registers().collect::<Vec<_>>(),
+      vec![(0, 8), (1, 4), (2, 8)]
+    );
+  }
+
+  #[test]
+  fn test_relative_addressing() {
+    let mut ram = ram_from("LOAD =42\nSTORE 5\nLOAD =2\nLOAD @3\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 42);
+
+    let mut ram = ram_from("LOAD =4\nSTORE @1\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.register(5), Some(4));
+
+    let mut ram = ram_from("LOAD =-3\nLOAD @1\nHALT");
+    assert_eq!(ram.run(), Err(InterpretError::SegmentationFault(2)));
+  }
+
+  #[test]
+  fn test_trace() {
+    let mut ram = ram_from("LOAD =3\nSTORE 1\nADD =2\nHALT");
+    assert!(ram.trace().is_empty());
+    ram.enable_trace();
+    ram.run().unwrap();
+
+    let trace = ram.trace();
+    assert_eq!(trace.len(), 4);
+    assert_eq!(
+      trace[0],
+      TraceEntry {
+        index: 0,
+        stmt: Stmt::Load(Value::Pure(3), 1),
+        accumulator_before: 0,
+        accumulator_after: 3,
+        register: None,
+      }
+    );
+    assert_eq!(trace[1].register, Some((1, 3)));
+    assert_eq!(trace[2].accumulator_before, 3);
+    assert_eq!(trace[2].accumulator_after, 5);
+    assert_eq!(trace[3].stmt, Stmt::Halt(4));
+  }
+
+  #[test]
+  fn test_execution_counts() {
+    let source = "LOAD =5\nloop: SUB =1\nJGTZ loop\nHALT";
+    let mut ram = ram_from(source);
+    ram.run().unwrap();
+
+    let counts = ram.execution_counts();
+    assert_eq!(counts.get(&0), Some(&1));
+    // `loop:` is instruction 1, its body starts at instruction 2.
+    assert_eq!(counts.get(&2), Some(&5));
+    assert_eq!(counts.get(&3), Some(&5));
+    assert_eq!(counts.get(&4), Some(&1));
+    assert_eq!(ram.total_steps(), counts.values().sum::<u64>() as usize);
+  }
+
+  #[test]
+  fn test_reset() {
+    let source = "READ 1\nLOAD 1\nMUL 1\nWRITE 0\nHALT";
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      Program::from_source(source).unwrap(),
+      Box::new(VecInput::from(vec![3])),
+      Box::new(output.clone()),
+    );
+    ram.run().unwrap();
+    assert_eq!(output.values(), vec![9]);
+
+    ram.reset_with_input(Box::new(VecInput::from(vec![4])));
+    output.clear();
+    assert_eq!(ram.total_steps(), 0);
+    assert_eq!(ram.register(1), None);
+
+    ram.run().unwrap();
+    assert_eq!(output.values(), vec![16]);
+    assert_eq!(ram.accumulator(), 16);
+    assert_eq!(ram.total_steps(), 5);
+  }
+
+  #[test]
+  fn test_accumulator_addressing() {
+    let mut ram = ram_from("LOAD =7\nSTORE 0\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 7);
+    assert_eq!(ram.registers().collect::<Vec<_>>(), vec![(0, 7)]);
+
+    let mut ram = ram_with_input("LOAD =7\nREAD 0\nHALT", vec![3]);
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 3);
+  }
+
+  #[test]
+  fn test_div_by_zero_policy() {
+    let run = |policy, source| {
+      let mut ram = ram_from(source).with_div_by_zero_policy(policy);
+      ram.run().map(|_| ram.accumulator())
+    };
+
+    for (policy, positive, negative) in [
+      (
+        DivByZeroPolicy::Error,
+        Err(InterpretError::DivisionByZero(2)),
+        Err(InterpretError::DivisionByZero(2)),
+      ),
+      (DivByZeroPolicy::Saturate, Ok(i64::MAX), Ok(i64::MIN)),
+      (DivByZeroPolicy::Zero, Ok(0), Ok(0)),
+    ] {
+      assert_eq!(run(policy, "LOAD =7\nDIV =0\nHALT"), positive);
+      assert_eq!(run(policy, "LOAD =-7\nDIV =0\nHALT"), negative);
+      assert_eq!(run(policy, "LOAD =7\nMOD =0\nHALT"), positive);
+    }
+    assert_eq!(run(DivByZeroPolicy::Saturate, "DIV =0\nHALT"), Ok(0));
+  }
+
+  #[test]
+  fn test_nop() {
+    let mut ram = ram_from("LOAD =3\nSTORE 1\nNOP\nHALT");
+    ram.step().unwrap();
+    ram.step().unwrap();
+    let before = ram.snapshot();
+
+    assert_eq!(ram.step(), Ok(StepResult::Running));
+    let after = ram.snapshot();
+    assert_eq!(after.pc, before.pc + 1);
+    assert_eq!(after.steps, before.steps + 1);
+    assert_eq!(after.registers, before.registers);
+    assert_eq!(after.halt, before.halt);
+  }
+
+  #[test]
+  fn test_large_register_index() {
+    let mut ram = ram_from("LOAD =5\nSTORE 1000000000000\nLOAD =0\nLOAD 1000000000000\nHALT");
+    ram.run().unwrap();
+
+    assert_eq!(ram.accumulator(), 5);
+    assert_eq!(ram.registers().count(), 2);
+    assert_eq!(
+      format!("{:?}", ram.snapshot().registers),
+      "{0: 5, 1000000000000: 5}"
+    );
+  }
+
+  #[test]
+  fn test_run_compiled() {
+    let source = "READ 1\nLOAD =1\nloop: MUL 1\nSTORE 2\nLOAD 1\nSUB =1\nSTORE 1\nLOAD 2\n\
+                  STORE 3\nLOAD 1\nJZERO end\nLOAD 3\nJUMP loop\nend: WRITE 3\nHALT";
+    let program = Program::from_source(source).unwrap();
+    let compiled = Compiled::try_from(&program).unwrap();
+
+    let run = |compiled: Option<&Compiled>| {
+      let output = VecOutput::default();
+      let mut ram = Ram::new(
+        program.clone(),
+        Box::new(VecInput::from(vec![5])),
+        Box::new(output.clone()),
+      );
+      match compiled {
+        Some(compiled) => ram.run_compiled(compiled).unwrap(),
+        None => ram.run().unwrap(),
+      }
+      (output.values(), ram.snapshot())
+    };
+
+    let (interpreted_output, interpreted) = run(None);
+    let (compiled_output, compiled) = run(Some(&compiled));
+    assert_eq!(interpreted_output, vec![120]);
+    assert_eq!(compiled_output, interpreted_output);
+    assert_eq!(compiled.registers, interpreted.registers);
+    assert_eq!(compiled.pc, interpreted.pc);
+    assert_eq!(compiled.line, interpreted.line);
+    assert_eq!(compiled.steps, interpreted.steps);
+    assert!(compiled.halt);
+  }
+
+  #[test]
+  fn test_overflow_policy() {
+    let run = |policy, source: &str| {
+      let mut ram = ram_from(source).with_overflow_policy(policy);
+      ram.run().map(|_| ram.accumulator())
+    };
+    let add = "LOAD =0x7fff_ffff_ffff_ffff\nADD =1\nHALT";
+    let sub = "LOAD =-0x7fff_ffff_ffff_ffff\nSUB =2\nHALT";
+
+    assert_eq!(run(OverflowPolicy::Wrap, add), Ok(i64::MIN));
+    assert_eq!(run(OverflowPolicy::Saturate, add), Ok(i64::MAX));
+    assert_eq!(
+      run(OverflowPolicy::Error, add),
+      Err(InterpretError::Overflow(2))
+    );
+    assert_eq!(run(OverflowPolicy::Wrap, sub), Ok(i64::MAX));
+    assert_eq!(run(OverflowPolicy::Saturate, sub), Ok(i64::MIN));
+    assert_eq!(
+      run(OverflowPolicy::default(), "LOAD =2\nPOW =64\nHALT"),
+      Err(InterpretError::Overflow(2))
+    );
+    assert_eq!(
+      run(
+        OverflowPolicy::Saturate,
+        "LOAD =-3\nMUL =0x7fff_ffff_ffff_ffff\nHALT"
+      ),
+      Ok(i64::MIN)
+    );
+  }
+
+  #[test]
+  fn test_with_input_str() {
+    let source = "READ 1\nREAD 2\nREAD 3\nLOAD 1\nADD 2\nADD 3\nHALT";
+    let mut ram = ram_from(source).with_input_str("3  7\n-2\n").unwrap();
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 8);
+
+    assert_eq!(
+      ram_from(source).with_input_str("3 x 7").err(),
+      Some(InputError::InvalidInput("x".to_string()))
+    );
+  }
+
+  #[test]
+  fn test_jump_less_zero() {
+    let source = "READ 0\nJLTZ negative\nLOAD =1\nHALT\nnegative: LOAD =-1\nHALT";
+    for (input, expected) in [(-5, -1), (0, 1), (5, 1)] {
+      let mut ram = ram_with_input(source, vec![input]);
+      ram.run().unwrap();
+      assert_eq!(ram.accumulator(), expected, "input {input}");
+    }
+  }
+
+  #[test]
+  fn test_jump_not_zero() {
+    let source = "LOAD =4\nloop: SUB =1\nSTORE 1\nLOAD 2\nADD =1\nSTORE 2\nLOAD 1\nJNZ loop\nHALT";
+    let mut ram = ram_from(source);
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 0);
+    assert_eq!(ram.register(2), Some(4));
+  }
+
+  #[test]
+  fn test_end_of_program_policy() {
+    let source = "LOAD =1\nADD =2";
+    let mut ram = ram_from(source);
+    assert_eq!(ram.run(), Err(InterpretError::FellOffEnd(2)));
+
+    let mut ram = ram_from(source).with_end_of_program_policy(EndOfProgramPolicy::Halt);
+    assert_eq!(ram.run(), Ok(()));
+    assert_eq!(ram.accumulator(), 3);
+    assert_eq!(ram.total_steps(), 2);
+    assert_eq!(ram.step(), Err(InterpretError::Halted(2)));
+
+    let program = Program::from_source(source).unwrap();
+    let compiled = Compiled::try_from(&program).unwrap();
+    let mut ram = ram_from(source).with_end_of_program_policy(EndOfProgramPolicy::Halt);
+    assert_eq!(ram.run_compiled(&compiled), Ok(()));
+    assert_eq!(ram.accumulator(), 3);
+  }
+
+  #[test]
+  fn test_output_accumulator() {
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      Program::from_source("LOAD =7\nOUTPUT\nADD =1\nWRITE\nHALT").unwrap(),
+      Box::new(VecInput::from(vec![])),
+      Box::new(output.clone()),
+    );
+    ram.run().unwrap();
+    assert_eq!(output.values(), vec![7, 8]);
+  }
+
+  #[test]
+  fn test_is_halted_and_pc() {
+    let mut ram = ram_from("LOAD =1\nADD =2\nHALT");
+    assert_eq!(ram.pc(), 0);
+    assert!(!ram.is_halted());
+
+    assert_eq!(ram.step(), Ok(StepResult::Running));
+    assert_eq!(ram.pc(), 1);
+    assert_eq!(ram.step(), Ok(StepResult::Running));
+    assert_eq!(ram.pc(), 2);
+    assert!(!ram.is_halted());
+
+    assert_eq!(ram.step(), Ok(StepResult::Halted));
+    assert!(ram.is_halted());
+  }
+
+  #[test]
+  fn test_set_register() {
+    let mut ram =
+      ram_from("LOAD 1\nADD 2\nADD 3\nHALT").with_register_init_policy(RegisterInitPolicy::Error);
+    for (index, value) in [(1, 4), (2, 5), (3, 6)] {
+      ram.set_register(index, value);
+    }
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 15);
+  }
+
+  #[test]
+  fn test_breakpoints() {
+    let mut ram = ram_from("LOAD =3\nloop: SUB =1\nJGTZ loop\nHALT");
+    ram.add_breakpoint(BreakAt::Label("loop".to_string()));
+
+    for accumulator in [3, 2, 1] {
+      assert_eq!(ram.run_to_breakpoint(), Ok(StopReason::Breakpoint(1)));
+      assert_eq!(ram.accumulator(), accumulator);
+    }
+    assert_eq!(ram.run_to_breakpoint(), Ok(StopReason::Halted));
+    assert_eq!(ram.accumulator(), 0);
+
+    ram.reset();
+    ram.add_breakpoint(BreakAt::Index(4));
+    assert!(ram.remove_breakpoint(&BreakAt::Label("loop".to_string())));
+    assert!(!ram.remove_breakpoint(&BreakAt::Label("loop".to_string())));
+    assert_eq!(ram.run_to_breakpoint(), Ok(StopReason::Breakpoint(4)));
+    assert_eq!(ram.get_current_instruction(), Some(Stmt::Halt(4)));
+    assert_eq!(ram.run_to_breakpoint(), Ok(StopReason::Halted));
+
+    // A breakpoint on the current instruction stops before executing it.
+    let mut ram = ram_from("a: WRITE =1\nJUMP a");
+    ram.add_breakpoint(BreakAt::Index(0));
+    assert_eq!(ram.run_to_breakpoint(), Ok(StopReason::Breakpoint(0)));
+    assert_eq!(ram.total_steps(), 0);
+    assert_eq!(ram.run_to_breakpoint(), Ok(StopReason::Breakpoint(0)));
+    assert_eq!(ram.total_steps(), 3);
+  }
+
+  #[test]
+  fn test_char_io() {
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      Program::from_source("a: READ 0\nWRITE 0\nSUB =33\nJNZ a\nHALT").unwrap(),
+      Box::new(std::io::Cursor::new("hé!rest")),
+      Box::new(output.clone()),
+    )
+    .with_char_io();
+    ram.run().unwrap();
+    assert_eq!(
+      output.values(),
+      "hé!".chars().map(|char| char as i64).collect::<Vec<_>>()
+    );
+
+    let mut ram = ram_from("LOAD =-5\nWRITE 0\nHALT").with_char_io();
+    assert_eq!(ram.run(), Err(InterpretError::InvalidChar(2, -5)));
+  }
+
+  #[test]
+  fn test_char_output() {
+    let output = CharOutput::default();
+    let mut ram = Ram::new(
+      Program::from_source("WRITE =72\nWRITE =73\nWRITE =1114112\nHALT").unwrap(),
+      Box::new(std::io::empty()),
+      Box::new(output.clone()),
+    );
+    assert_eq!(ram.run(), Err(InterpretError::InvalidChar(3, 1_114_112)));
+    assert_eq!(output.text(), "HI");
+  }
+
+  #[test]
+  fn test_run_yielding() {
+    let mut ram = ram_from("LOAD =3\nloop: WRITE 0\nSUB =1\nJGTZ loop\nHALT");
+    let events: Vec<_> = ram.run_yielding().collect();
+    assert_eq!(
+      events,
+      vec![
+        Ok(Event::Output(3)),
+        Ok(Event::Output(2)),
+        Ok(Event::Output(1)),
+        Ok(Event::Halted),
+      ]
+    );
+    assert_eq!(ram.run_yielding().next(), None);
+
+    let mut ram = ram_from("WRITE =1\nDIV =0\nHALT");
+    let events: Vec<_> = ram.run_yielding().collect();
+    assert_eq!(
+      events,
+      vec![Ok(Event::Output(1)), Err(InterpretError::DivisionByZero(2))]
+    );
+
+    let mut ram = ram_from("WRITE =1").with_end_of_program_policy(EndOfProgramPolicy::Halt);
+    let events: Vec<_> = ram.run_yielding().collect();
+    assert_eq!(events, vec![Ok(Event::Output(1)), Ok(Event::Halted)]);
+  }
+
+  #[test]
+  fn test_max_register() {
+    let mut ram = ram_from("LOAD =1\nSTORE 4\nSTORE 5\nHALT").with_max_register(4);
+    assert_eq!(
+      ram.run(),
+      Err(InterpretError::RegisterIndexOutOfRange(3, 5))
+    );
+    assert_eq!(ram.register(4), Some(1));
+
+    let mut ram = ram_from("LOAD 9\nHALT").with_max_register(4);
+    assert_eq!(
+      ram.run(),
+      Err(InterpretError::RegisterIndexOutOfRange(1, 9))
+    );
+
+    let mut ram = ram_from("LOAD =1000000\nSTORE 1\nSTORE *1\nHALT").with_max_register(4);
+    assert_eq!(
+      ram.run(),
+      Err(InterpretError::RegisterIndexOutOfRange(3, 1_000_000))
+    );
+
+    let mut ram = ram_from("LOAD =2\nLOAD @3\nHALT").with_max_register(4);
+    assert_eq!(
+      ram.run(),
+      Err(InterpretError::RegisterIndexOutOfRange(2, 5))
+    );
+  }
+
+  #[test]
+  fn test_negative_indirect_index() {
+    let mut ram = ram_from("LOAD =-1\nSTORE 3\nLOAD *3\nHALT");
+    assert_eq!(ram.run(), Err(InterpretError::NegativeIndirectIndex(3)));
+
+    let mut ram = ram_from("LOAD =-1\nSTORE 3\nSTORE *3\nHALT");
+    assert_eq!(ram.run(), Err(InterpretError::NegativeIndirectIndex(3)));
+
+    let mut ram = ram_with_input("LOAD =-1\nSTORE 3\nREAD *3\nHALT", vec![5]);
+    assert_eq!(ram.run(), Err(InterpretError::NegativeIndirectIndex(3)));
+  }
+
+  #[test]
+  fn test_builder() {
+    let output = VecOutput::default();
+    let mut ram = Ram::builder()
+      .with_input(Box::new(VecInput::from(vec![i64::MAX])))
+      .with_output(Box::new(output.clone()))
+      .with_overflow_policy(OverflowPolicy::Saturate)
+      .with_end_of_program_policy(EndOfProgramPolicy::Halt)
+      .with_max_register(2)
+      .build(Program::from_source("READ 1\nLOAD 1\nADD =1\nWRITE 0").unwrap());
+    ram.run().unwrap();
+    assert_eq!(output.values(), vec![i64::MAX]);
+
+    let mut ram = Ram::builder()
+      .with_max_steps(3)
+      .build(Program::from_source("a: JUMP a").unwrap());
+    assert_eq!(ram.run(), Err(InterpretError::StepLimitExceeded(1)));
+
+    let mut ram = RamBuilder::new().build(Program::from_source("LOAD 1\nHALT").unwrap());
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 0);
+  }
+
+  #[test]
+  fn test_inc_dec() {
+    let mut ram = ram_from("LOAD =7\nINC 1\nINC 1\nDEC 2\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 7);
+    assert_eq!(ram.register(1), Some(2));
+    assert_eq!(ram.register(2), Some(-1));
+
+    let mut ram = ram_from("LOAD =5\nSTORE 1\nLOAD =1\nINC *1\nDEC @2\nINC 0\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.register(5), Some(1));
+    assert_eq!(ram.register(3), Some(-1));
+    assert_eq!(ram.accumulator(), 2);
+
+    let mut ram = ram_from("LOAD =9223372036854775807\nSTORE 1\nINC 1\nHALT");
+    assert_eq!(ram.run(), Err(InterpretError::Overflow(3)));
+  }
+
+  #[test]
+  fn test_swap() {
+    let mut ram = ram_from("LOAD =3\nSTORE 1\nLOAD =8\nSWAP 1\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 3);
+    assert_eq!(ram.register(1), Some(8));
+
+    let mut ram = ram_from("LOAD =5\nSTORE 1\nLOAD =-2\nSTORE 5\nLOAD =7\nSWAP *1\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), -2);
+    assert_eq!(ram.register(5), Some(7));
+    assert_eq!(ram.register(1), Some(5));
+
+    let mut ram = ram_from("LOAD =4\nSWAP 0\nHALT");
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 4);
+  }
+
+  #[test]
+  fn test_exit_code() {
+    let mut ram = ram_from("LOAD =1\nJZ zero\nHALT 3\nzero: HALT");
+    assert_eq!(ram.exit_code(), 0);
+    ram.run().unwrap();
+    assert!(ram.is_halted());
+    assert_eq!(ram.exit_code(), 3);
+    assert_eq!(ram.snapshot().exit_code, 3);
+
+    ram.reset();
+    assert_eq!(ram.exit_code(), 0);
+
+    let mut ram = ram_from("HALT\nHALT 1");
+    ram.run().unwrap();
+    assert_eq!(ram.exit_code(), 0);
+
+    let mut ram = ram_from("HALT -2");
+    ram.step().unwrap();
+    assert_eq!(ram.exit_code(), -2);
+  }
+
+  #[test]
+  fn test_state_diff() {
+    let mut reference = ram_from("LOAD =4\nSTORE 1\nLOAD =9\nSTORE 2\nHALT");
+    reference.run().unwrap();
+    let mut student = ram_from("LOAD =4\nSTORE 1\nLOAD =9\nSTORE 3\nHALT");
+    student.run().unwrap();
+
+    let diff = reference.snapshot().diff(&student.snapshot());
+    assert_eq!(
+      diff,
+      StateDiff {
+        accumulator: None,
+        registers: vec![(2, Some(9), None), (3, None, Some(9))],
+      }
+    );
+    assert!(!diff.is_empty());
+    assert!(reference.snapshot().diff(&reference.snapshot()).is_empty());
+
+    student.set_register(0, 1);
+    let diff = reference.snapshot().diff(&student.snapshot());
+    assert_eq!(diff.accumulator, Some((9, 1)));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_state_serde_round_trip() {
+    let mut ram = ram_from("LOAD =4\nSTORE 7\nHALT 2");
+    ram.run().unwrap();
+    let state = ram.snapshot();
+
+    let json = serde_json::to_string(&state).unwrap();
+    let restored: RamState = serde_json::from_str(&json).unwrap();
+    assert!(restored.diff(&state).is_empty());
+    assert_eq!(restored.registers, state.registers);
+    assert_eq!(restored.program.instructions, state.program.instructions);
+    assert_eq!(restored.program.labels, state.program.labels);
+    assert_eq!(
+      (restored.pc, restored.halt, restored.exit_code),
+      (3, true, 2)
+    );
+  }
+
+  #[test]
+  fn test_watch_register() {
+    let changes = Rc::new(RefCell::new(Vec::new()));
+    let mut ram = ram_with_input(
+      "READ 2\nINC 2\nDEC 2\nLOAD =9\nSWAP 2\nSTORE 3\nSTORE 2\nLOAD =2\nSTORE 1\nLOAD =8\nSTORE *1\nHALT",
+      vec![5],
+    );
+    let log = changes.clone();
+    ram.watch_register(2, move |old, new| log.borrow_mut().push((old, new)));
+    ram.run().unwrap();
+
+    assert_eq!(
+      *changes.borrow(),
+      vec![
+        (None, 5),
+        (Some(5), 6),
+        (Some(6), 5),
+        (Some(5), 9),
+        (Some(9), 5),
+        (Some(5), 8),
+      ]
+    );
+
+    let changes = Rc::new(RefCell::new(Vec::new()));
+    let mut ram = ram_from("LOAD =4\nSTORE 1\nLOAD =7\nSWAP 1\nSWAP 0\nHALT");
+    let log = changes.clone();
+    ram.watch_register(0, move |old, new| log.borrow_mut().push((old, new)));
+    ram.run().unwrap();
+    assert_eq!(*changes.borrow(), vec![(Some(7), 4), (Some(4), 4)]);
+  }
+
+  #[test]
+  fn test_output_limit() {
+    let output = VecOutput::default();
+    let mut ram = Ram::builder()
+      .with_output(Box::new(output.clone()))
+      .with_max_output(3)
+      .build(Program::from_source("LOAD =7\nloop: WRITE 0\nJUMP loop").unwrap());
+
+    assert_eq!(ram.run(), Err(InterpretError::OutputLimitExceeded(2)));
+    assert_eq!(ram.output_count(), 3);
+    assert_eq!(output.values(), vec![7, 7, 7]);
+
+    let mut ram = ram_from("WRITE =1\nWRITE =2\nHALT").with_max_output(2);
+    ram.run().unwrap();
+    assert_eq!(ram.output_count(), 2);
+    ram.reset();
+    assert_eq!(ram.output_count(), 0);
+  }
+
+  #[test]
+  fn test_forward_and_backward_jumps() {
+    let source = "LOAD =3\nJUMP body\nloop: SUB =1\nJZ end\nbody: WRITE 0\nJUMP loop\nend: HALT";
+    let output = VecOutput::default();
+    let mut ram = Ram::new(
+      Program::from_source(source).unwrap(),
+      Box::new(std::io::empty()),
+      Box::new(output.clone()),
+    );
+    ram.run().unwrap();
+    assert_eq!(output.values(), vec![3, 2, 1]);
+
+    let errors = Program::from_source("JZ missing\nHALT\nend: HALT").unwrap_err();
+    assert_eq!(
+      errors,
+      vec![ParseError::UnknownLabel(1, "missing".to_string())]
+    );
+  }
+
+  #[test]
+  fn test_simulate() {
+    let source = "loop: READ 1\nLOAD 1\nJZ end\nMUL 1\nWRITE 0\nJUMP loop\nend: HALT";
+    assert_eq!(simulate(source, &[3, -4, 0]), Ok(vec![9, 16]));
+
+    assert_eq!(
+      simulate("LOAD =1\nFOO 2\nJUMP nowhere", &[]),
+      Err(SimError::Parse(vec![ParseError::UnsupportedOpcode(
+        2,
+        Span::new(0, 3),
+        "FOO".to_string()
+      )]))
+    );
+    assert_eq!(
+      simulate("READ 1\nHALT", &[]),
+      Err(SimError::Interpret(InterpretError::NoInput(1)))
+    );
+    assert_eq!(
+      simulate("a: JUMP a", &[]),
+      Err(SimError::Interpret(InterpretError::StepLimitExceeded(1)))
+    );
+  }
+
+  #[test]
+  fn test_label_address() {
+    let program = Program::from_source("LOAD =@end\nNOP\nend: HALT").unwrap();
+    assert_eq!(program.get(0), Some(&Stmt::Load(Value::Pure(2), 1)));
+    let mut ram = Ram::new(
+      program,
+      Box::new(std::io::empty()),
+      Box::new(std::io::sink()),
+    );
+    ram.run().unwrap();
+    assert_eq!(ram.accumulator(), 2);
+
+    // A jump table: the input selects which address to jump to.
+    let source =
+      "READ 1\nLOAD =@a\nSTORE 2\nLOAD =@b\nSTORE 3\nLOAD 1\nADD =2\nSTORE 4\nLOAD *4\nJIND 0\n\
+      a: WRITE =10\nHALT\nb: WRITE =20\nHALT";
+    for (input, expected) in [(0, 10), (1, 20)] {
+      let output = VecOutput::default();
+      let mut ram = Ram::new(
+        Program::from_source(source).unwrap(),
+        Box::new(VecInput::from(vec![input])),
+        Box::new(output.clone()),
+      );
+      ram.run().unwrap();
+      assert_eq!(output.values(), vec![expected]);
+    }
+
+    let program = Program::from(crate::parser::parse_collect("LOAD =@missing\nHALT").unwrap());
+    let mut ram = Ram::new(
+      program,
+      Box::new(std::io::empty()),
+      Box::new(std::io::sink()),
+    );
+    assert_eq!(ram.run(), Err(InterpretError::UnknownLabel(1)));
+  }
+
+  #[test]
+  fn test_run_with_timeout() {
+    let mut ram = ram_from("a: JUMP a").with_timeout_check_interval(10);
+    assert_eq!(
+      ram.run_with_timeout(Duration::from_millis(1)),
+      Err(InterpretError::Timeout(1))
+    );
+    assert!(ram.is_halted());
+    assert_eq!(ram.total_steps() % 10, 0);
+
+    let mut ram = Ram::builder()
+      .with_timeout_check_interval(0)
+      .build(Program::from_source("LOAD =1\nHALT").unwrap());
+    assert_eq!(ram.run_with_timeout(Duration::from_secs(60)), Ok(()));
+    assert_eq!(ram.accumulator(), 1);
   }
 }
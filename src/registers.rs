@@ -6,7 +6,7 @@
 //! # Examples
 //!
 //! ```
-//! use ram::registers::Registers;
+//! use ramemu::registers::Registers;
 //!
 //! let mut registers = Registers::default();
 //! registers.set(0, 42);
@@ -30,10 +30,13 @@ use std::iter::FromIterator;
 /// of registers. This allows for efficient access to the registers and dynamic
 /// growth of the register set.
 ///
+/// Registers are stored sparsely, so only the registers that have been set take up memory,
+/// no matter how large their indices are.
+///
 /// # Examples
 ///
 /// ```
-/// use ram::registers::Registers;
+/// use ramemu::registers::Registers;
 ///
 /// let mut registers = Registers::default();
 /// registers.set(0, 42);
@@ -43,6 +46,7 @@ use std::iter::FromIterator;
 /// assert_eq!(registers.get(1), 24);
 /// ```
 #[derive(Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(transparent)]
 pub struct Registers<T> {
   registers: RefCell<HashMap<usize, T>>,
@@ -56,26 +60,66 @@ impl<T: Clone + Default> Registers<T> {
   /// # Examples
   ///
   /// ```
-  /// use ram::registers::Registers;
+  /// use ramemu::registers::Registers;
   ///
   /// let registers: Registers<u8> = Registers::default();
   /// assert_eq!(registers.get(4), 0);
   /// ```
   #[inline]
   pub fn get(&self, index: usize) -> T {
-    let value = {
-      let mut map = self.registers.borrow_mut();
-      let value = map.entry(index).or_insert_with(T::default);
-      value.clone()
-    };
-    value
+    self.try_get(index).unwrap_or_default()
   }
+  /// Returns the value of the register at the given index, or `None` if the register
+  /// has not been set.
+  ///
+  /// Unlike [`Registers::get`], this never initializes the register.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use ramemu::registers::Registers;
+  ///
+  /// let mut registers = Registers::default();
+  /// registers.set(0, 42);
+  /// assert_eq!(registers.try_get(0), Some(42));
+  /// assert_eq!(registers.try_get(1), None);
+  /// ```
+  #[inline]
+  pub fn try_get(&self, index: usize) -> Option<T> {
+    self.registers.borrow().get(&index).cloned()
+  }
+
+  /// Returns an iterator over the indices and values of the registers that have been set,
+  /// in ascending index order.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use ramemu::registers::Registers;
+  ///
+  /// let mut registers = Registers::default();
+  /// registers.set(3, 7);
+  /// registers.set(1, 5);
+  ///
+  /// assert_eq!(registers.iter().collect::<Vec<_>>(), vec![(1, 5), (3, 7)]);
+  /// ```
+  pub fn iter(&self) -> impl Iterator<Item = (usize, T)> {
+    let mut registers: Vec<(usize, T)> = self
+      .registers
+      .borrow()
+      .iter()
+      .map(|(index, value)| (*index, value.clone()))
+      .collect();
+    registers.sort_unstable_by_key(|(index, _)| *index);
+    registers.into_iter()
+  }
+
   /// Sets the value of the register at the given index.
   ///
   /// # Examples
   ///
   /// ```
-  /// use ram::registers::Registers;
+  /// use ramemu::registers::Registers;
   ///
   /// let mut registers = Registers::default();
   /// registers.set(0, 42);
@@ -89,11 +133,7 @@ impl<T: Clone + Default> Registers<T> {
 
 impl<T: Clone + Default + Debug> Debug for Registers<T> {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-    let max_index = *self.registers.borrow().keys().max().unwrap_or(&0);
-    let vec: Vec<T> = (0..=max_index)
-      .map(|i| self.registers.borrow().get(&i).cloned().unwrap_or_default())
-      .collect();
-    Debug::fmt(&vec, f)
+    f.debug_map().entries(self.iter()).finish()
   }
 }
 
@@ -101,7 +141,7 @@ impl<T> FromIterator<T> for Registers<T> {
   #[inline]
   fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
     Registers {
-      registers: RefCell::new(iter.into_iter().enumerate().map(|(i, v)| (i, v)).collect()),
+      registers: RefCell::new(iter.into_iter().enumerate().collect()),
     }
   }
 }
@@ -118,7 +158,7 @@ impl<T> FromIterator<(usize, T)> for Registers<T> {
 impl<T, const N: usize> From<[T; N]> for Registers<T> {
   #[inline]
   fn from(value: [T; N]) -> Self {
-    Self::from_iter(value.into_iter())
+    Self::from_iter(value)
   }
 }
 
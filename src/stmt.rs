@@ -58,14 +58,28 @@
 //! This code demonstrates the use of various statements, including Load, Store, Jump, and Label.
 //! It also demonstrates the use of `Value` and `RegisterValue` for specifying operands in the
 //! assembly language code.
+//!
+//! # Serialization
+//!
+//! With the `serde` feature enabled, [`Stmt`], [`Value`], [`RegisterValue`] and [`Label`]
+//! implement `Serialize` and `Deserialize`. Enums use serde's default externally tagged
+//! representation, with tuple variants serialized as arrays and [`Label`] as a plain string.
+//! For example, `LOAD =5` on line 1 is serialized to JSON as `{"Load":[{"Pure":5},1]}` and
+//! `JUMP end` on line 2 as `{"Jump":["end",2]}`.
+
+use alloc::string::String;
+use alloc::string::ToString;
 
 /// Represents a statement in the program, along with its line number from the source code.
 /// Statements are the basic building blocks of a program and define the operations to be performed.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Stmt {
   /// Loads value into register `0`
   Load(Value, usize),
   /// Stores value from register `0` into register
+  ///
+  /// Register `0` is the accumulator, so `STORE 0` leaves the machine unchanged.
   Store(RegisterValue, usize),
   /// Adds value to register `0`
   Add(Value, usize),
@@ -75,26 +89,170 @@ pub enum Stmt {
   Mul(Value, usize),
   /// Divides register `0` by value
   Div(Value, usize),
+  /// Sets register `0` to the remainder of dividing it by value
+  Mod(Value, usize),
+  /// Raises register `0` to the power of value
+  Pow(Value, usize),
+  /// Adds `1` to register, leaving register `0` unchanged unless it is the register
+  Inc(RegisterValue, usize),
+  /// Subtracts `1` from register, leaving register `0` unchanged unless it is the register
+  Dec(RegisterValue, usize),
+  /// Exchanges the values of register `0` and register
+  Swap(RegisterValue, usize),
   /// Jumps to label
   Jump(Label, usize),
   /// Jumps to label if register `0` is equal to `0`
   JumpIfZero(Label, usize),
   /// Jumps to label if register `0` is greater than `0`
-  JumpGreatherZero(Label, usize),
+  #[cfg_attr(feature = "serde", serde(alias = "JumpGreatherZero"))]
+  JumpGreaterZero(Label, usize),
+  /// Jumps to label if register `0` is not equal to `0`
+  JumpNotZero(Label, usize),
+  /// Jumps to label if register `0` is less than `0`
+  JumpLessZero(Label, usize),
+  /// Jumps to the instruction whose index is stored in register
+  JumpIndirect(RegisterValue, usize),
   /// Inputs value from `reader`
+  ///
+  /// Register `0` is the accumulator, so `READ 0` loads the input value into it.
   Input(RegisterValue, usize),
   /// Outputs value to `writer`
+  ///
+  /// `WRITE` and `OUTPUT` without an argument output the accumulator, like `WRITE 0`.
   Output(Value, usize),
   /// Represents label
   Label(String, usize),
+  /// Defines the label name, first, as an alias of the target label, second. Jumps to the
+  /// alias land where the target is defined. Does nothing when executed.
+  Alias(String, Label, usize),
+  /// Does nothing, execution continues with the next instruction
+  Nop(usize),
   /// Halts program
   Halt(usize),
+  /// Halts program with the given exit code, written as `HALT 1`. A plain `HALT` exits with
+  /// code `0`.
+  HaltCode(isize, usize),
 }
 
 impl Stmt {
+  /// Creates a [`Stmt::JumpGreaterZero`], under its former misspelled name.
+  #[deprecated(note = "use `Stmt::JumpGreaterZero` instead")]
+  #[allow(non_snake_case)]
+  #[inline]
+  pub fn JumpGreatherZero(label: Label, line: usize) -> Stmt {
+    Stmt::JumpGreaterZero(label, line)
+  }
+
   /// Returns line number of statement in source code
   #[inline]
+  pub fn line(&self) -> usize {
+    match self {
+      Stmt::Load(_, line)
+      | Stmt::Store(_, line)
+      | Stmt::Add(_, line)
+      | Stmt::Sub(_, line)
+      | Stmt::Mul(_, line)
+      | Stmt::Div(_, line)
+      | Stmt::Mod(_, line)
+      | Stmt::Pow(_, line)
+      | Stmt::Inc(_, line)
+      | Stmt::Dec(_, line)
+      | Stmt::Swap(_, line)
+      | Stmt::Jump(_, line)
+      | Stmt::JumpIfZero(_, line)
+      | Stmt::JumpGreaterZero(_, line)
+      | Stmt::JumpNotZero(_, line)
+      | Stmt::JumpLessZero(_, line)
+      | Stmt::JumpIndirect(_, line)
+      | Stmt::Input(_, line)
+      | Stmt::Output(_, line)
+      | Stmt::Label(_, line)
+      | Stmt::Alias(_, _, line)
+      | Stmt::Nop(line)
+      | Stmt::Halt(line)
+      | Stmt::HaltCode(_, line) => *line,
+    }
+  }
+
+  /// Returns the canonical mnemonic of the statement, as used by its [`Display`] output,
+  /// e.g. `"LOAD"` or `"JZERO"`. Labels return `"LABEL"`.
+  ///
+  /// [`Display`]: core::fmt::Display
+  pub fn opcode(&self) -> &'static str {
+    match self {
+      Stmt::Load(..) => "LOAD",
+      Stmt::Store(..) => "STORE",
+      Stmt::Add(..) => "ADD",
+      Stmt::Sub(..) => "SUB",
+      Stmt::Mul(..) => "MUL",
+      Stmt::Div(..) => "DIV",
+      Stmt::Mod(..) => "MOD",
+      Stmt::Pow(..) => "POW",
+      Stmt::Inc(..) => "INC",
+      Stmt::Dec(..) => "DEC",
+      Stmt::Swap(..) => "SWAP",
+      Stmt::Jump(..) => "JUMP",
+      Stmt::JumpIfZero(..) => "JZERO",
+      Stmt::JumpGreaterZero(..) => "JGTZ",
+      Stmt::JumpNotZero(..) => "JNZ",
+      Stmt::JumpLessZero(..) => "JLTZ",
+      Stmt::JumpIndirect(..) => "JIND",
+      Stmt::Input(..) => "READ",
+      Stmt::Output(..) => "WRITE",
+      Stmt::Label(..) => "LABEL",
+      Stmt::Alias(..) => "ALIAS",
+      Stmt::Nop(_) => "NOP",
+      Stmt::Halt(_) | Stmt::HaltCode(..) => "HALT",
+    }
+  }
+
+  /// Returns line number of statement in source code, same as [`Stmt::line`].
+  #[inline]
   pub fn get_line(&self) -> usize {
+    self.line()
+  }
+
+  /// Sets the line number of the statement, e.g. after moving it in a transformation.
+  #[inline]
+  pub fn set_line(&mut self, line: usize) {
+    *self.line_mut() = line;
+  }
+
+  /// Returns `true` if both statements are equal apart from their line numbers.
+  ///
+  /// # Examples
+  ///
+  /// ```
+  /// use ramemu::stmt::Stmt;
+  ///
+  /// assert!(Stmt::Halt(1).eq_ignoring_line(&Stmt::Halt(7)));
+  /// assert!(!Stmt::Halt(1).eq_ignoring_line(&Stmt::Nop(1)));
+  /// ```
+  pub fn eq_ignoring_line(&self, other: &Stmt) -> bool {
+    let mut other = other.clone();
+    other.set_line(self.line());
+    *self == other
+  }
+
+  /// Returns the [`Value`] operand of the statement, if it has one.
+  #[cfg(feature = "std")]
+  #[inline]
+  pub(crate) fn value_mut(&mut self) -> Option<&mut Value> {
+    match self {
+      Stmt::Load(value, _)
+      | Stmt::Add(value, _)
+      | Stmt::Sub(value, _)
+      | Stmt::Mul(value, _)
+      | Stmt::Div(value, _)
+      | Stmt::Mod(value, _)
+      | Stmt::Pow(value, _)
+      | Stmt::Output(value, _) => Some(value),
+      _ => None,
+    }
+  }
+
+  #[inline]
+  fn line_mut(&mut self) -> &mut usize {
     match self {
       Stmt::Load(_, line)
       | Stmt::Store(_, line)
@@ -102,39 +260,109 @@ impl Stmt {
       | Stmt::Sub(_, line)
       | Stmt::Mul(_, line)
       | Stmt::Div(_, line)
+      | Stmt::Mod(_, line)
+      | Stmt::Pow(_, line)
+      | Stmt::Inc(_, line)
+      | Stmt::Dec(_, line)
+      | Stmt::Swap(_, line)
       | Stmt::Jump(_, line)
       | Stmt::JumpIfZero(_, line)
-      | Stmt::JumpGreatherZero(_, line)
+      | Stmt::JumpGreaterZero(_, line)
+      | Stmt::JumpNotZero(_, line)
+      | Stmt::JumpLessZero(_, line)
+      | Stmt::JumpIndirect(_, line)
       | Stmt::Input(_, line)
       | Stmt::Output(_, line)
       | Stmt::Label(_, line)
-      | Stmt::Halt(line) => *line,
+      | Stmt::Alias(_, _, line)
+      | Stmt::Nop(line)
+      | Stmt::Halt(line)
+      | Stmt::HaltCode(_, line) => line,
+    }
+  }
+}
+
+impl core::fmt::Display for Stmt {
+  /// Formats the statement as canonical assembly, e.g. `LOAD =5`, `STORE *3` or `loop:`.
+  ///
+  /// The line number is not part of the output. Re-parsing the output yields the same
+  /// statement.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Stmt::Load(value, _) => write!(f, "LOAD {value}"),
+      Stmt::Store(value, _) => write!(f, "STORE {value}"),
+      Stmt::Add(value, _) => write!(f, "ADD {value}"),
+      Stmt::Sub(value, _) => write!(f, "SUB {value}"),
+      Stmt::Mul(value, _) => write!(f, "MUL {value}"),
+      Stmt::Div(value, _) => write!(f, "DIV {value}"),
+      Stmt::Mod(value, _) => write!(f, "MOD {value}"),
+      Stmt::Pow(value, _) => write!(f, "POW {value}"),
+      Stmt::Inc(value, _) => write!(f, "INC {value}"),
+      Stmt::Dec(value, _) => write!(f, "DEC {value}"),
+      Stmt::Swap(value, _) => write!(f, "SWAP {value}"),
+      Stmt::Jump(label, _) => write!(f, "JUMP {label}"),
+      Stmt::JumpIfZero(label, _) => write!(f, "JZERO {label}"),
+      Stmt::JumpGreaterZero(label, _) => write!(f, "JGTZ {label}"),
+      Stmt::JumpNotZero(label, _) => write!(f, "JNZ {label}"),
+      Stmt::JumpLessZero(label, _) => write!(f, "JLTZ {label}"),
+      Stmt::JumpIndirect(value, _) => write!(f, "JIND {value}"),
+      Stmt::Input(value, _) => write!(f, "READ {value}"),
+      Stmt::Output(value, _) => write!(f, "WRITE {value}"),
+      Stmt::Label(label, _) => write!(f, "{label}:"),
+      Stmt::Alias(name, target, _) => write!(f, "ALIAS {name} {target}"),
+      Stmt::Nop(_) => write!(f, "NOP"),
+      Stmt::Halt(_) => write!(f, "HALT"),
+      Stmt::HaltCode(code, _) => write!(f, "HALT {code}"),
     }
   }
 }
 
 /// Represents a value that can be passed to a statement.
-/// The value can be a pure numeric value or a value stored in a register.
+/// The value can be a pure numeric value, a value stored in a register or the address of a
+/// label.
 ///
 /// Examples:
 /// - `LOAD =5`: Loads the pure numeric value `5` into register 0.
 /// - `LOAD 5`: Loads the value stored in register 5 into register 0.
+/// - `LOAD =@end`: Loads the index of the instruction labeled `end` into register 0.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
   /// Represents a pure numeric value, indicated by an equal sign (`=`) before the number.
   /// For example, `LOAD =5` loads the pure numeric value `5` into register 0.
+  /// The value is signed and may carry a leading `-` or `+`, e.g. `LOAD =-5`.
   Pure(isize),
   /// Represents the value stored in a specific register.
   // /// For example, `LOAD 5` loads the value stored in register 5 into register 0.
   Register(RegisterValue),
+  /// Represents the address of a label, i.e. the index of the instruction it labels, written
+  /// as `=@name`. Building a [`Program`] resolves it into a [`Value::Pure`] holding the index,
+  /// so together with `JIND` it can be used to build jump tables.
+  ///
+  /// [`Program`]: ../program/struct.Program.html
+  LabelAddr(Label),
 }
 
-/// Represents a register that can be operated on directly or indirectly.
+impl core::fmt::Display for Value {
+  /// Formats pure values as `=5`, register values as in [`RegisterValue`] and label
+  /// addresses as `=@name`.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Value::Pure(value) => write!(f, "={value}"),
+      Value::Register(register) => write!(f, "{register}"),
+      Value::LabelAddr(label) => write!(f, "=@{label}"),
+    }
+  }
+}
+
+/// Represents a register that can be operated on directly, indirectly or relative to the accumulator.
 ///
-/// There are two ways to specify the register to be operated on:
+/// There are three ways to specify the register to be operated on:
 /// - Direct: The register is specified directly, e.g., `STORE 2` stores the value from register 0 into register 2.
 /// - Indirect: The register is specified indirectly, e.g., `STORE *2` stores the value from register 0 into the register whose number is stored in register 2.
+/// - Relative: The register is specified as a displacement from the accumulator, e.g., `LOAD @3` loads the value of the register whose number is the value of register 0 plus 3.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RegisterValue {
   /// Specifies the register to be operated on directly.
   /// Example: `STORE 2` stores the value from register 0 into register 2.
@@ -142,10 +370,38 @@ pub enum RegisterValue {
   /// Specifies the register to be operated on indirectly.
   /// Example: `STORE *2` stores the value from register 0 into the register whose number is stored in register 2.
   Indirect(usize),
+  /// Specifies the register to be operated on relative to the accumulator.
+  /// Example: `LOAD @3` loads the value from the register whose number is the value of register 0 plus 3.
+  Relative(usize),
+}
+
+impl core::fmt::Display for RegisterValue {
+  /// Formats direct registers as `5`, indirect registers as `*5` and relative registers as `@5`.
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    match self {
+      RegisterValue::Direct(index) => write!(f, "{index}"),
+      RegisterValue::Indirect(index) => write!(f, "*{index}"),
+      RegisterValue::Relative(offset) => write!(f, "@{offset}"),
+    }
+  }
 }
 
 /// Represent label
+///
+/// # Examples
+///
+/// ```
+/// use ramemu::stmt::Label;
+///
+/// let label = Label::from("loop");
+/// assert_eq!(label.name(), "loop");
+/// assert_eq!(label.to_string(), "loop");
+///
+/// let name: &str = label.as_ref();
+/// assert_eq!(name, "loop");
+/// ```
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Label(String);
 
 impl Label {
@@ -159,4 +415,210 @@ impl Label {
   pub fn get(&self) -> &str {
     &self.0
   }
+  /// Returns the name of the label, the same as [`Label::get`].
+  #[inline]
+  pub fn name(&self) -> &str {
+    &self.0
+  }
+}
+
+impl From<&str> for Label {
+  /// Creates a label from its name.
+  ///
+  /// ```
+  /// use ramemu::stmt::Label;
+  ///
+  /// assert_eq!(Label::from("end"), Label::new("end".to_string()));
+  /// ```
+  #[inline]
+  fn from(label: &str) -> Self {
+    Label(label.to_string())
+  }
+}
+
+impl From<String> for Label {
+  /// Creates a label from its name.
+  ///
+  /// ```
+  /// use ramemu::stmt::Label;
+  ///
+  /// let label: Label = String::from("end").into();
+  /// assert_eq!(label.name(), "end");
+  /// ```
+  #[inline]
+  fn from(label: String) -> Self {
+    Label(label)
+  }
+}
+
+impl AsRef<str> for Label {
+  /// Borrows the name of the label.
+  ///
+  /// ```
+  /// use ramemu::stmt::Label;
+  ///
+  /// fn is_loop(label: impl AsRef<str>) -> bool {
+  ///   label.as_ref() == "loop"
+  /// }
+  /// assert!(is_loop(Label::from("loop")));
+  /// ```
+  #[inline]
+  fn as_ref(&self) -> &str {
+    &self.0
+  }
+}
+
+impl core::fmt::Display for Label {
+  /// Formats the label as its name, e.g. `loop`.
+  ///
+  /// ```
+  /// use ramemu::stmt::Label;
+  ///
+  /// assert_eq!(format!("JUMP {}", Label::from("loop")), "JUMP loop");
+  /// ```
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    f.write_str(&self.0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::parser::{parse, parse_line};
+
+  #[test]
+  fn test_display() {
+    assert_eq!(Stmt::Load(Value::Pure(5), 1).to_string(), "LOAD =5");
+    assert_eq!(
+      Stmt::Store(RegisterValue::Indirect(3), 1).to_string(),
+      "STORE *3"
+    );
+    assert_eq!(
+      Stmt::Add(Value::Register(RegisterValue::Direct(2)), 1).to_string(),
+      "ADD 2"
+    );
+    assert_eq!(
+      Stmt::JumpIfZero(Label::new("end".to_string()), 1).to_string(),
+      "JZERO end"
+    );
+    assert_eq!(Stmt::Label("loop".to_string(), 1).to_string(), "loop:");
+    assert_eq!(Stmt::Nop(1).to_string(), "NOP");
+    assert_eq!(Stmt::Halt(1).to_string(), "HALT");
+  }
+
+  #[test]
+  fn test_display_round_trip() {
+    let source = "
+      start:
+      read 1
+      LOAD =-5
+      add *1
+      load @2
+      store @0
+      sub 2
+      mul =3
+      div =2
+      mod =7
+      pow =2
+      store *4
+      jz start
+      jgz end
+      jmp start
+      jind *3
+      end: write 0
+      halt
+    ";
+    let stmts: Vec<Stmt> = parse(source).collect::<Result<_, _>>().unwrap();
+    assert_eq!(stmts.len(), 19);
+
+    for stmt in stmts {
+      let reparsed = parse_line(&stmt.to_string(), stmt.get_line()).unwrap();
+      assert_eq!(reparsed, vec![stmt]);
+    }
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_round_trip() {
+    let stmts = vec![
+      Stmt::Label("start".to_string(), 1),
+      Stmt::Load(Value::Pure(5), 1),
+      Stmt::Store(RegisterValue::Indirect(3), 2),
+      Stmt::Add(Value::Register(RegisterValue::Direct(1)), 3),
+      Stmt::Jump(Label::new("start".to_string()), 4),
+      Stmt::Halt(5),
+    ];
+
+    let json = serde_json::to_string(&stmts).unwrap();
+    assert!(json.starts_with(r#"[{"Label":["start",1]},{"Load":[{"Pure":5},1]}"#));
+    assert!(json.contains(r#"{"Jump":["start",4]}"#));
+
+    let deserialized: Vec<Stmt> = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, stmts);
+  }
+
+  #[test]
+  fn test_eq_ignoring_line() {
+    let first = parse_line("LOAD =5", 1).unwrap();
+    let second = parse_line("load =5", 9).unwrap();
+    assert_ne!(first, second);
+    assert!(first[0].eq_ignoring_line(&second[0]));
+
+    let other = parse_line("LOAD 5", 1).unwrap();
+    assert!(!first[0].eq_ignoring_line(&other[0]));
+  }
+
+  #[test]
+  fn test_line() {
+    let mut stmts = parse("LOAD =1\nloop: STORE 2\n\nJUMP loop\nNOP\nHALT")
+      .collect::<Result<Vec<_>, _>>()
+      .unwrap();
+    let lines: Vec<_> = stmts.iter().map(Stmt::line).collect();
+    assert_eq!(lines, vec![1, 2, 2, 4, 5, 6]);
+
+    for stmt in &mut stmts {
+      stmt.set_line(stmt.line() * 10);
+    }
+    let lines: Vec<_> = stmts.iter().map(Stmt::get_line).collect();
+    assert_eq!(lines, vec![10, 20, 20, 40, 50, 60]);
+  }
+
+  #[test]
+  fn test_opcode() {
+    let source =
+      "LOAD =1\nSTORE 1\nADD 1\nSUB 1\nMUL 1\nDIV 1\nMOD 1\nPOW 1\nINC 1\nDEC 1\nSWAP 1\nl: JMP l\nJZ l\n\
+      JGZ l\nJLZ l\nJNEZERO l\nJIND 1\nINPUT 1\nOUTPUT 1\nALIAS m l\nNOP\nHALT\nHALT 1";
+    let opcodes: Vec<_> = parse(source).map(|stmt| stmt.unwrap().opcode()).collect();
+    assert_eq!(
+      opcodes,
+      vec![
+        "LOAD", "STORE", "ADD", "SUB", "MUL", "DIV", "MOD", "POW", "INC", "DEC", "SWAP", "LABEL",
+        "JUMP", "JZERO", "JGTZ", "JLTZ", "JNZ", "JIND", "READ", "WRITE", "ALIAS", "NOP", "HALT",
+        "HALT",
+      ]
+    );
+
+    // The mnemonic matches the one used by `Display`.
+    for stmt in parse(source).map(Result::unwrap) {
+      if !matches!(stmt, Stmt::Label(..)) {
+        assert!(stmt.to_string().starts_with(stmt.opcode()));
+      }
+    }
+  }
+
+  #[test]
+  #[allow(deprecated)]
+  fn test_deprecated_names() {
+    assert_eq!(
+      Stmt::JumpGreatherZero(Label::new("a".to_string()), 1),
+      Stmt::JumpGreaterZero(Label::new("a".to_string()), 1)
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serde_accepts_misspelled_name() {
+    let stmt: Stmt = serde_json::from_str(r#"{"JumpGreatherZero":["a",1]}"#).unwrap();
+    assert_eq!(stmt, Stmt::JumpGreaterZero(Label::new("a".to_string()), 1));
+  }
 }
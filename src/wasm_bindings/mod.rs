@@ -1 +1,140 @@
+//! The `wasm_bindings` module exposes the parser and the RAM machine to JavaScript through
+//! `wasm-bindgen`.
+//!
+//! [`parse_to_json`] parses source code into the JSON representation of its statements, see
+//! the [`stmt`](crate::stmt) module. [`WasmMachine`] loads and runs a program. Errors are
+//! thrown as JavaScript exceptions carrying the error message, e.g.
+//! `line 2: unknown opcode 'FOO'`.
+//!
+//! # Examples
+//!
+//! ```js
+//! import init, { parse_to_json, WasmMachine } from "./pkg/ramemu.js";
+//!
+//! await init();
+//! console.log(JSON.parse(parse_to_json("LOAD =1\nHALT")));
+//!
+//! const machine = new WasmMachine();
+//! machine.load("READ 1\nLOAD 1\nMUL =2\nWRITE 0\nHALT", [21n]);
+//! machine.run();
+//! console.log(machine.output()); // BigInt64Array [42n]
+//! ```
+use wasm_bindgen::prelude::*;
 
+use crate::io::VecInput;
+use crate::io::VecOutput;
+use crate::parser;
+use crate::program::Program;
+use crate::ram::Ram;
+use crate::ram::StepResult;
+use crate::stmt::Stmt;
+
+/// Parses the source code and returns its statements serialized as a JSON array.
+///
+/// Throws the first parse error.
+#[wasm_bindgen]
+pub fn parse_to_json(source: &str) -> Result<String, JsError> {
+  let stmts = parser::parse(source).collect::<Result<Vec<Stmt>, _>>()?;
+  Ok(serde_json::to_string(&stmts)?)
+}
+
+/// A RAM machine that can be driven from JavaScript.
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmMachine {
+  ram: Option<Ram>,
+  output: VecOutput,
+}
+
+#[wasm_bindgen]
+impl WasmMachine {
+  /// Creates a machine without a program, see [`WasmMachine::load`].
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> WasmMachine {
+    WasmMachine::default()
+  }
+
+  /// Parses the source code and loads it with the given input values, replacing the
+  /// previous program and clearing the output.
+  ///
+  /// Throws an exception listing every error in the source, one per line.
+  pub fn load(&mut self, source: &str, input: Vec<i64>) -> Result<(), JsError> {
+    let program = Program::from_source(source).map_err(|errors| {
+      let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+      JsError::new(&messages.join("\n"))
+    })?;
+    self.output = VecOutput::default();
+    self.ram = Some(Ram::new(
+      program,
+      Box::new(VecInput::from(input)),
+      Box::new(self.output.clone()),
+    ));
+    Ok(())
+  }
+
+  /// Executes a single instruction. Returns `true` while the machine is still running.
+  pub fn step(&mut self) -> Result<bool, JsError> {
+    Ok(self.ram()?.step()? == StepResult::Running)
+  }
+
+  /// Runs the program until it halts.
+  pub fn run(&mut self) -> Result<(), JsError> {
+    Ok(self.ram()?.run()?)
+  }
+
+  /// Returns the value of the accumulator.
+  pub fn accumulator(&self) -> i64 {
+    self.ram.as_ref().map_or(0, Ram::accumulator)
+  }
+
+  /// Returns the registers that have been set as a JSON array of `[index, value]` pairs.
+  pub fn registers(&self) -> String {
+    let registers: Vec<(usize, i64)> = self
+      .ram
+      .as_ref()
+      .map(|ram| ram.registers().collect())
+      .unwrap_or_default();
+    serde_json::to_string(&registers).unwrap_or_default()
+  }
+
+  /// Returns the values written by the program so far.
+  pub fn output(&self) -> Vec<i64> {
+    self.output.values()
+  }
+}
+
+impl WasmMachine {
+  fn ram(&mut self) -> Result<&mut Ram, JsError> {
+    self
+      .ram
+      .as_mut()
+      .ok_or_else(|| JsError::new("no program is loaded"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_to_json() {
+    assert_eq!(
+      parse_to_json("LOAD =5\nHALT").unwrap(),
+      r#"[{"Load":[{"Pure":5},1]},{"Halt":2}]"#
+    );
+  }
+
+  #[test]
+  fn test_wasm_machine() {
+    let mut machine = WasmMachine::new();
+    machine
+      .load("READ 1\nLOAD 1\nMUL =2\nWRITE 0\nHALT", vec![21])
+      .unwrap();
+
+    assert!(machine.step().unwrap());
+    machine.run().unwrap();
+    assert_eq!(machine.accumulator(), 42);
+    assert_eq!(machine.registers(), "[[0,42],[1,21]]");
+    assert_eq!(machine.output(), vec![42]);
+  }
+}